@@ -1,174 +1,444 @@
-use std::str::FromStr;
+use std::{error::Error, fmt, str::FromStr};
 
-pub enum Instruction {
+use itertools::Itertools;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Register {
+    X,
+    Y,
+    Z,
+}
+
+const REGISTER_COUNT: usize = 3;
+
+impl Register {
+    fn from_char(c: char) -> Option<Register> {
+        match c {
+            'x' => Some(Register::X),
+            'y' => Some(Register::Y),
+            'z' => Some(Register::Z),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Register::X => 'x',
+            Register::Y => 'y',
+            Register::Z => 'z',
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Opcode {
     Noop,
-    Addx(i64),
+    Add,
+    Sub,
+    Mul,
+    Set,
+}
+
+struct OpcodeInfo {
+    opcode: Opcode,
+    mnemonic: &'static str,
+    cycles: u32,
+    takes_operand: bool,
+    effect: fn(i64, i64) -> i64,
+}
+
+// Arithmetic wraps around like a real register would instead of panicking
+const ISA: [OpcodeInfo; 5] = [
+    OpcodeInfo {
+        opcode: Opcode::Noop,
+        mnemonic: "noop",
+        cycles: 1,
+        takes_operand: false,
+        effect: |register, _| register,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Add,
+        mnemonic: "add",
+        cycles: 2,
+        takes_operand: true,
+        effect: |register, value| register.wrapping_add(value),
+    },
+    OpcodeInfo {
+        opcode: Opcode::Sub,
+        mnemonic: "sub",
+        cycles: 2,
+        takes_operand: true,
+        effect: |register, value| register.wrapping_sub(value),
+    },
+    OpcodeInfo {
+        opcode: Opcode::Mul,
+        mnemonic: "mul",
+        cycles: 3,
+        takes_operand: true,
+        effect: |register, value| register.wrapping_mul(value),
+    },
+    OpcodeInfo {
+        opcode: Opcode::Set,
+        mnemonic: "set",
+        cycles: 1,
+        takes_operand: true,
+        effect: |_, value| value,
+    },
+];
+
+impl Opcode {
+    fn info(self) -> &'static OpcodeInfo {
+        ISA.iter().find(|info| info.opcode == self).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub register: Register,
+    pub value: i64,
+}
+
+impl Instruction {
+    pub const NOOP: Instruction = Instruction {
+        opcode: Opcode::Noop,
+        register: Register::X,
+        value: 0,
+    };
+
+    pub fn addx(value: i64) -> Instruction {
+        Instruction {
+            opcode: Opcode::Add,
+            register: Register::X,
+            value,
+        }
+    }
+
+    fn cycles(&self) -> u32 {
+        self.opcode.info().cycles
+    }
+
+    fn apply(&self, registers: &mut [i64; REGISTER_COUNT]) {
+        let register = &mut registers[self.register as usize];
+        *register = (self.opcode.info().effect)(*register, self.value);
+    }
 }
 
 impl FromStr for Instruction {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "noop" {
-            Ok(Self::Noop)
-        } else if s.starts_with("addx ") {
-            Ok(Self::Addx(s.trim_start_matches("addx ").parse().unwrap()))
+        let mut words = s.split_whitespace();
+        let mnemonic = words.next().ok_or_else(|| String::from("Empty line"))?;
+
+        let (opcode, register) = ISA
+            .iter()
+            .find_map(|info| {
+                if info.takes_operand {
+                    let mut suffix = mnemonic.strip_prefix(info.mnemonic)?.chars();
+                    let register = Register::from_char(suffix.next()?)?;
+                    suffix.next().is_none().then_some((info.opcode, register))
+                } else {
+                    (mnemonic == info.mnemonic).then_some((info.opcode, Register::X))
+                }
+            })
+            .ok_or_else(|| format!("Unknown opcode `{mnemonic}`"))?;
+
+        let value = if opcode.info().takes_operand {
+            let operand = words
+                .next()
+                .ok_or_else(|| format!("Missing operand for `{mnemonic}`"))?;
+            operand
+                .parse()
+                .map_err(|_| format!("Invalid operand `{operand}`"))?
         } else {
-            Err(String::from("Invalid"))
+            0
+        };
+
+        match words.next() {
+            Some(extra) => Err(format!("Unexpected `{extra}` after `{mnemonic}`")),
+            None => Ok(Instruction {
+                opcode,
+                register,
+                value,
+            }),
         }
     }
 }
 
-#[aoc_generator(day10)]
-fn input_generator(input: &str) -> Vec<Instruction> {
-    input.lines().flat_map(Instruction::from_str).collect()
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.opcode.info();
+
+        if info.takes_operand {
+            write!(
+                f,
+                "{}{} {}",
+                info.mnemonic,
+                self.register.to_char(),
+                self.value
+            )
+        } else {
+            write!(f, "{}", info.mnemonic)
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
 }
 
-fn execute_cycle(
-    cycle_amount: &i64,
-    x: &i64,
-    executed_already: &mut bool,
-    signal_strenght: &mut i64,
-) {
-    if !*executed_already
-        && (*cycle_amount == 20 || ((*cycle_amount as f64 - 20.0) / 40.0).fract() == 0.0)
-    {
-        let total = *cycle_amount * x;
-        *signal_strenght += total;
-        *executed_already = true;
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} in `{}`", self.line, self.reason, self.text)
     }
 }
 
-#[aoc(day10, part1)]
-pub fn part1(instructions: &[Instruction]) -> i64 {
-    let mut signal_strenght = 0;
-    let mut x: i64 = 1;
-
-    let mut add_next_cycle: i64 = 0;
-    let mut cycle_amount = 0;
-    for instruction in instructions {
-        let mut executed_already = false;
-        execute_cycle(
-            &cycle_amount,
-            &x,
-            &mut executed_already,
-            &mut signal_strenght,
-        );
+impl Error for AssembleError {}
+
+pub fn assemble(input: &str) -> Result<Vec<Instruction>, AssembleError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.parse().map_err(|reason| AssembleError {
+                line: i + 1,
+                text: line.to_string(),
+                reason,
+            })
+        })
+        .collect()
+}
 
-        x += add_next_cycle;
-        add_next_cycle = 0;
+pub fn disassemble(program: &[Instruction]) -> String {
+    program.iter().join("\n")
+}
 
-        if let Instruction::Addx(i) = instruction {
-            add_next_cycle = *i;
-            cycle_amount += 1;
-        }
+#[aoc_generator(day10)]
+fn input_generator(input: &str) -> Vec<Instruction> {
+    assemble(input).unwrap_or_else(|e| panic!("{e}"))
+}
 
-        execute_cycle(
-            &cycle_amount,
-            &x,
-            &mut executed_already,
-            &mut signal_strenght,
-        );
+pub struct Cpu<'a> {
+    instructions: std::slice::Iter<'a, Instruction>,
+    current: Option<&'a Instruction>,
+    cycles_left: u32,
+    cycle: i64,
+    registers: [i64; REGISTER_COUNT],
+}
 
-        cycle_amount += 1;
+impl<'a> Cpu<'a> {
+    pub fn new(instructions: &'a [Instruction]) -> Self {
+        Self {
+            instructions: instructions.iter(),
+            current: None,
+            cycles_left: 0,
+            cycle: 0,
+            registers: [1; REGISTER_COUNT],
+        }
     }
 
-    signal_strenght
+    pub fn register(&self, register: Register) -> i64 {
+        self.registers[register as usize]
+    }
 }
 
-// fn sprite_pos_preview(pos: &i64) -> String {
-//     (1..=40)
-//         .map(|i| {
-//             if i == *pos || i == pos - 1 || i == pos + 1 {
-//                 '#'
-//             } else {
-//                 '.'
-//             }
-//         })
-//         .collect()
-// }
+impl Iterator for Cpu<'_> {
+    type Item = (i64, i64);
 
-// fn display_row(row: &[bool]) -> String {
-//     row.iter()
-//         .map(|pixel| if *pixel { '#' } else { '.' })
-//         .collect()
-// }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cycles_left == 0 {
+            let instruction = self.instructions.next()?;
+            self.cycles_left = instruction.cycles();
+            self.current = Some(instruction);
+        }
 
-// fn execute_cycle2(crt_cycle: &mut i64, x: &i64, crt: &mut [Vec<bool>]) {
-//     let row = match crt_cycle {
-//         0..=40 => 0,
-//         41..=80 => 1,
-//         81..=120 => 2,
-//         121..=160 => 3,
-//         161..=200 => 4,
-//         201..=240 => 5,
-//         _ => 6,
-//     };
+        self.cycle += 1;
+        let during = (self.cycle, self.register(Register::X));
 
-//     if row == 0 {
-//         let pos = *crt_cycle - 1;
+        self.cycles_left -= 1;
+        if let Some(instruction) = self.current.filter(|_| self.cycles_left == 0) {
+            instruction.apply(&mut self.registers);
+        }
 
-//         if pos == x - 1 || pos == *x || pos == x + 1 {
-//             crt[row][pos as usize] = true
-//         }
+        Some(during)
+    }
+}
 
-//         println!(
-//             "During cycle   {}: CRT draws pixel in position {}",
-//             crt_cycle, pos
-//         );
+pub fn x_during(instructions: &[Instruction], cycle: i64) -> Option<i64> {
+    Cpu::new(instructions)
+        .find(|(c, _)| *c == cycle)
+        .map(|(_, x)| x)
+}
 
-//         println!("Current CRT row: {}", display_row(&crt[row]));
-//     }
+#[aoc(day10, part1)]
+pub fn part1(instructions: &[Instruction]) -> i64 {
+    Cpu::new(instructions)
+        .filter(|(cycle, _)| cycle % 40 == 20)
+        .map(|(cycle, x)| cycle * x)
+        .sum()
+}
 
-//     *crt_cycle += 1;
-// }
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_SPACING: usize = 1;
+
+const FONT: [(char, [&str; CRT_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+pub type Framebuffer = [[bool; CRT_WIDTH]; CRT_HEIGHT];
+
+pub struct Crt {
+    pixels: Framebuffer,
+}
+
+impl Crt {
+    fn new() -> Self {
+        Self {
+            pixels: [[false; CRT_WIDTH]; CRT_HEIGHT],
+        }
+    }
 
-// #[aoc(day10, part2)]
-// pub fn part2(instructions: &[Instruction]) -> u64 {
-//     let mut crt = vec![vec![false; 39]; 6];
+    fn draw(&mut self, cycle: i64, x: i64) {
+        let index = (cycle - 1) as usize;
+        let (row, column) = (index / CRT_WIDTH, index % CRT_WIDTH);
 
-//     let mut x: i64 = 1;
-//     let mut add_next_cycle: i64 = 0;
+        if row < CRT_HEIGHT && (column as i64).abs_diff(x) <= 1 {
+            self.pixels[row][column] = true;
+        }
+    }
 
-//     let mut crt_cycle = 1;
-//     let mut cycle_amount = 0;
-//     for instruction in instructions {
-//         println!("Sprite position: {}", sprite_pos_preview(&x));
-//         println!();
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.pixels
+    }
 
-//         execute_cycle2(&mut crt_cycle, &x, &mut crt);
+    pub fn render(&self) -> String {
+        self.pixels
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|pixel| if *pixel { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
 
-//         x += add_next_cycle;
-//         add_next_cycle = 0;
+    fn glyph(&self, index: usize) -> Option<char> {
+        let offset = index * (GLYPH_WIDTH + GLYPH_SPACING);
+
+        FONT.iter()
+            .find(|(_, rows)| {
+                rows.iter()
+                    .zip(self.pixels.iter())
+                    .all(|(glyph_row, pixel_row)| {
+                        glyph_row
+                            .chars()
+                            .zip(&pixel_row[offset..offset + GLYPH_WIDTH])
+                            .all(|(c, pixel)| (c == '#') == *pixel)
+                    })
+            })
+            .map(|(letter, _)| *letter)
+    }
 
-//         if let Instruction::Addx(i) = instruction {
-//             println!("Start cycle   {}: being executing addx {}", crt_cycle, i);
-//             add_next_cycle = *i;
-//             cycle_amount += 1;
-//         }
+    pub fn read_letters(&self) -> String {
+        (0..CRT_WIDTH / (GLYPH_WIDTH + GLYPH_SPACING))
+            .map(|i| self.glyph(i).unwrap_or('?'))
+            .collect()
+    }
+}
 
-//         // execute_cycle2(&mut crt_cycle, &x, &mut crt);
+pub fn run_crt(instructions: &[Instruction]) -> Crt {
+    let mut crt = Crt::new();
 
-//         cycle_amount += 1;
-//     }
+    for (cycle, x) in Cpu::new(instructions) {
+        crt.draw(cycle, x);
+    }
 
-//     for row in crt {
-//         println!("{}", display_row(&row));
-//     }
-//     println!();
+    crt
+}
 
-//     todo!()
-// }
+#[aoc(day10, part2)]
+pub fn part2(instructions: &[Instruction]) -> String {
+    run_crt(instructions).read_letters()
+}
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day10::{input_generator, part1};
+    use crate::day10::{
+        Cpu, Instruction, Register, assemble, disassemble, input_generator, part1, part2, run_crt,
+        x_during,
+    };
 
     #[test]
     fn test_day10() {
         let input = input_generator(&read_to_string("input/2022/day10.txt").unwrap());
         assert_eq!(14340, part1(&input));
+        assert_eq!("PAPJCBHP", part2(&input));
+
+        let crt = run_crt(&input);
+        assert_eq!(
+            "###...##..###....##..##..###..#..#.###..",
+            crt.render().lines().next().unwrap()
+        );
+        assert!(crt.framebuffer()[5][0] && !crt.framebuffer()[5][1]);
+
+        assert_eq!(Some(1), x_during(&input, 1));
+        assert_eq!(None, x_during(&input, 241));
+
+        let raw = read_to_string("input/2022/day10.txt").unwrap();
+        assert_eq!(raw.trim_end(), disassemble(&input));
+    }
+
+    #[test]
+    fn test_day10_isa() {
+        let program = assemble("sety 5\nmuly 3\nnoop\naddx -2\nsubz 4").unwrap();
+        assert_eq!(Instruction::NOOP, program[2]);
+        assert_eq!(Instruction::addx(-2), program[3]);
+        assert_eq!(
+            "sety 5\nmuly 3\nnoop\naddx -2\nsubz 4",
+            disassemble(&program)
+        );
+
+        let mut cpu = Cpu::new(&program);
+        assert_eq!(9, cpu.by_ref().count());
+        assert_eq!(15, cpu.register(Register::Y));
+        assert_eq!(-1, cpu.register(Register::X));
+        assert_eq!(-3, cpu.register(Register::Z));
+
+        let err = assemble("noop\n\naddx 1\njmpx 3").unwrap_err();
+        assert_eq!(4, err.line);
+        assert_eq!("jmpx 3", err.text);
+        assert!(assemble("addx").is_err());
+        assert!(assemble("noop 1").is_err());
+
+        let program = assemble(&"mulx 1000\n".repeat(8)).unwrap();
+        let mut cpu = Cpu::new(&program);
+        cpu.by_ref().for_each(drop);
+        assert_eq!(1000i64.wrapping_pow(8), cpu.register(Register::X));
     }
 }
@@ -1,131 +1,617 @@
+use std::{error::Error, fmt, str::FromStr};
+
 use itertools::Itertools;
+use serde::Serialize;
 
-struct Monkey {
+pub struct Monkey {
+    pub id: u16,
     inspected_items: i64,
     items: Vec<i64>,
-    operation: Box<dyn Fn(i64) -> i64>,
+    pub operation: Expr,
 
     divisible_by: i64,
-    test: Box<dyn Fn(i64) -> u16>,
+    // Indices into the list of monkeys, not ids
+    if_true: usize,
+    if_false: usize,
 }
 
-fn sign_to_func(s: &str) -> fn(i64, i64) -> Option<i64> {
-    match s {
-        "*" => i64::checked_mul,
-        "+" => i64::checked_add,
-        _ => unreachable!(),
+impl Monkey {
+    fn target(&self, worry_level: i64) -> usize {
+        if worry_level % self.divisible_by == 0 {
+            self.if_true
+        } else {
+            self.if_false
+        }
     }
 }
 
-impl Monkey {
-    fn from_lines(lines: [&str; 6]) -> Option<Monkey> {
-        let items: Vec<i64> = lines[1]
-            .strip_prefix("  Starting items: ")?
-            .split(", ")
-            .map(|num| num.parse().unwrap())
-            .collect();
-
-        let operation_arguments: Vec<&str> = lines[2]
-            .strip_prefix("  Operation: new = ")?
-            .split_whitespace()
-            .collect();
-
-        let sign = sign_to_func(operation_arguments[1]);
-        let operation: Box<dyn Fn(i64) -> i64> =
-            if operation_arguments[0] == "old" && operation_arguments[2] == "old" {
-                Box::new(move |x: i64| sign(x, x).unwrap())
-            } else {
-                let arg = operation_arguments[2].parse().ok()?;
-                Box::new(move |x: i64| sign(x, arg).unwrap())
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+    StartingItems,
+    Operation,
+    Test,
+    IfTrue,
+    IfFalse,
+}
+
+const FIELDS: [(&str, Field); 5] = [
+    ("starting items", Field::StartingItems),
+    ("operation", Field::Operation),
+    ("test", Field::Test),
+    ("if true", Field::IfTrue),
+    ("if false", Field::IfFalse),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    NoMonkeys,
+    FieldOutsideMonkey,
+    InvalidHeader,
+    DuplicateMonkey,
+    UnknownField,
+    DuplicateField,
+    MissingField,
+    InvalidNumber,
+    InvalidOperation,
+    UnknownOperator,
+    DivisionByZero,
+    InvalidTest,
+    InvalidTarget,
+    UnknownMonkey,
+}
+
+// `monkey` is the id from the header of the block the error is in, `field` is
+// only missing when the error isn't about a known field
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub monkey: Option<u16>,
+    pub field: Option<Field>,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.monkey {
+            Some(id) => write!(f, "monkey {id}")?,
+            None => write!(f, "input")?,
+        }
+        if let Some(field) = self.field {
+            write!(f, ", {field:?}")?;
+        }
+        write!(f, ": {:?}", self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    fn from_symbol(c: char) -> Option<Op> {
+        Some(match c {
+            '+' => Op::Add,
+            '-' => Op::Sub,
+            '*' => Op::Mul,
+            '/' => Op::Div,
+            _ => return None,
+        })
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+
+    fn apply(&self, lhs: i64, rhs: i64) -> Result<i64, EvalError> {
+        let result = match self {
+            Op::Add => lhs.checked_add(rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => lhs.checked_mul(rhs),
+            Op::Div if rhs == 0 => return Err(EvalError::DivisionByZero),
+            Op::Div => lhs.checked_div(rhs),
+        };
+
+        result.ok_or(EvalError::Overflow(lhs, *self, rhs))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvalError {
+    Overflow(i64, Op, i64),
+    DivisionByZero,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Overflow(lhs, op, rhs) => {
+                write!(f, "{lhs} {} {rhs} overflows", op.symbol())
+            }
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+// The right hand side of `new = ...`
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum Expr {
+    Old,
+    Const(i64),
+    BinOp(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, old: i64) -> Result<i64, EvalError> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Const(value) => Ok(*value),
+            Expr::BinOp(lhs, op, rhs) => op.apply(lhs.eval(old)?, rhs.eval(old)?),
+        }
+    }
+
+    pub fn uses_old(&self) -> bool {
+        match self {
+            Expr::Old => true,
+            Expr::Const(_) => false,
+            Expr::BinOp(lhs, _, rhs) => lhs.uses_old() || rhs.uses_old(),
+        }
+    }
+
+    // Whether `eval(old % m) % m == eval(old) % m` for every modulus m, which
+    // is what lets part 2 keep worry levels modulo the product of all the
+    // divisors without changing where items go. Sums, differences and
+    // products keep congruences, division only does when nothing under it
+    // depends on the old value
+    pub fn commutes_with_mod(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => true,
+            Expr::BinOp(lhs, Op::Div, rhs) => !lhs.uses_old() && !rhs.uses_old(),
+            Expr::BinOp(lhs, _, rhs) => lhs.commutes_with_mod() && rhs.commutes_with_mod(),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::BinOp(_, op, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+// Only adds the parentheses parsing the output back needs. Everything is left
+// associative, so the right operand also gets them on equal precedence
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(value) => write!(f, "{value}"),
+            Expr::BinOp(lhs, op, rhs) => {
+                if lhs.precedence() < op.precedence() {
+                    write!(f, "({lhs})")?;
+                } else {
+                    write!(f, "{lhs}")?;
+                }
+
+                write!(f, " {} ", op.symbol())?;
+
+                if rhs.precedence() <= op.precedence() {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token {
+    Old,
+    Number(i64),
+    Op(Op),
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_alphanumeric() => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric()) {
+                    word.push(c);
+                }
+
+                tokens.push(match word.as_str() {
+                    "old" => Token::Old,
+                    _ => Token::Number(word.parse().map_err(|_| ErrorKind::InvalidNumber)?),
+                });
+            }
+            c => tokens.push(Token::Op(
+                Op::from_symbol(c).ok_or(ErrorKind::UnknownOperator)?,
+            )),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Precedence climbing, `min` is the weakest operator the caller still takes
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self, min: u8) -> Result<Expr, ErrorKind> {
+        let mut lhs = self.operand()?;
+
+        loop {
+            let op = match self.tokens.get(self.pos) {
+                Some(Token::Op(op)) if op.precedence() >= min => *op,
+                _ => return Ok(lhs),
             };
+            self.pos += 1;
+
+            let rhs = self.expr(op.precedence() + 1)?;
+            if op == Op::Div && rhs == Expr::Const(0) {
+                return Err(ErrorKind::DivisionByZero);
+            }
+
+            lhs = Expr::BinOp(Box::new(lhs), op, Box::new(rhs));
+        }
+    }
+
+    fn operand(&mut self) -> Result<Expr, ErrorKind> {
+        match self.next() {
+            Some(Token::Old) => Ok(Expr::Old),
+            Some(Token::Number(value)) => Ok(Expr::Const(value)),
+            Some(Token::Op(Op::Sub)) => match self.next() {
+                Some(Token::Number(value)) => Ok(Expr::Const(-value)),
+                _ => Err(ErrorKind::InvalidOperation),
+            },
+            Some(Token::Open) => {
+                let expr = self.expr(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(ErrorKind::InvalidOperation),
+                }
+            }
+            _ => Err(ErrorKind::InvalidOperation),
+        }
+    }
+}
 
-        let divisible_by = lines[3]
-            .strip_prefix("  Test: divisible by ")?
-            .parse()
-            .ok()?;
-
-        let true_monkey = lines[4]
-            .strip_prefix("    If true: throw to monkey ")?
-            .parse()
-            .ok()?;
-
-        let false_monkey = lines[5]
-            .strip_prefix("    If false: throw to monkey ")?
-            .parse()
-            .ok()?;
-
-        let test: Box<dyn Fn(i64) -> u16> = Box::new(move |x: i64| {
-            if x % divisible_by == 0 {
-                true_monkey
-            } else {
-                false_monkey
+impl FromStr for Expr {
+    type Err = ErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.expr(0)?;
+
+        if parser.pos != parser.tokens.len() {
+            return Err(ErrorKind::InvalidOperation);
+        }
+
+        Ok(expr)
+    }
+}
+
+fn parse_operation(s: &str) -> Result<Expr, ErrorKind> {
+    let (new, expr) = s.split_once('=').ok_or(ErrorKind::InvalidOperation)?;
+    if new.trim() != "new" {
+        return Err(ErrorKind::InvalidOperation);
+    }
+
+    expr.parse()
+}
+
+// Only the last word is looked at, everything before it has to read `words`
+fn parse_sentence<T: FromStr>(s: &str, words: &str, mismatch: ErrorKind) -> Result<T, ErrorKind> {
+    let (prefix, last) = s.trim().rsplit_once(char::is_whitespace).ok_or(mismatch)?;
+
+    if !prefix.split_whitespace().eq(words.split_whitespace()) {
+        return Err(mismatch);
+    }
+
+    last.parse().map_err(|_| ErrorKind::InvalidNumber)
+}
+
+fn parse_field(field: Field, value: &str, draft: &mut Draft) -> Result<(), ErrorKind> {
+    match field {
+        Field::StartingItems => {
+            draft.items = Some(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| item.parse().map_err(|_| ErrorKind::InvalidNumber))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+        Field::Operation => draft.operation = Some(parse_operation(value)?),
+        Field::Test => {
+            let divisible_by = parse_sentence(value, "divisible by", ErrorKind::InvalidTest)?;
+            if divisible_by == 0 {
+                return Err(ErrorKind::DivisionByZero);
+            }
+            draft.divisible_by = Some(divisible_by);
+        }
+        Field::IfTrue => {
+            draft.if_true = Some(parse_sentence(
+                value,
+                "throw to monkey",
+                ErrorKind::InvalidTarget,
+            )?)
+        }
+        Field::IfFalse => {
+            draft.if_false = Some(parse_sentence(
+                value,
+                "throw to monkey",
+                ErrorKind::InvalidTarget,
+            )?)
+        }
+    }
+
+    Ok(())
+}
+
+// A monkey while its block is still being read, targets are still ids here
+#[derive(Default)]
+struct Draft {
+    id: u16,
+    items: Option<Vec<i64>>,
+    operation: Option<Expr>,
+    divisible_by: Option<i64>,
+    if_true: Option<u16>,
+    if_false: Option<u16>,
+}
+
+impl Draft {
+    fn has(&self, field: Field) -> bool {
+        match field {
+            Field::StartingItems => self.items.is_some(),
+            Field::Operation => self.operation.is_some(),
+            Field::Test => self.divisible_by.is_some(),
+            Field::IfTrue => self.if_true.is_some(),
+            Field::IfFalse => self.if_false.is_some(),
+        }
+    }
+}
+
+fn parse_header(line: &str) -> Option<Result<u16, ErrorKind>> {
+    let (keyword, rest) = line.split_at_checked(6)?;
+    if !keyword.eq_ignore_ascii_case("monkey") {
+        return None;
+    }
+
+    let id = rest.trim().strip_suffix(':').unwrap_or(rest).trim();
+    Some(id.parse().map_err(|_| ErrorKind::InvalidHeader))
+}
+
+// Monkeys start at their `Monkey n:` header, so blank lines and indentation
+// don't matter. Fields can come in any order and names ignore case. Monkeys
+// are returned sorted by id with throw targets resolved to indices
+pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let mut drafts: Vec<Draft> = Vec::new();
+
+    for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let monkey = drafts.last().map(|draft| draft.id);
+        let error = |field, kind| ParseError {
+            monkey,
+            field,
+            kind,
+        };
+
+        if let Some(id) = parse_header(line) {
+            // The header is what names the monkey, so a broken one can't
+            // be blamed on the monkey before it
+            let id = id.map_err(|kind| ParseError {
+                monkey: None,
+                field: None,
+                kind,
+            })?;
+            if drafts.iter().any(|draft| draft.id == id) {
+                return Err(ParseError {
+                    monkey: Some(id),
+                    field: None,
+                    kind: ErrorKind::DuplicateMonkey,
+                });
             }
+
+            drafts.push(Draft {
+                id,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| error(None, ErrorKind::UnknownField))?;
+        let name = name.split_whitespace().join(" ").to_lowercase();
+        let (_, field) = FIELDS
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .ok_or_else(|| error(None, ErrorKind::UnknownField))?;
+
+        let draft = drafts
+            .last_mut()
+            .ok_or_else(|| error(Some(*field), ErrorKind::FieldOutsideMonkey))?;
+        if draft.has(*field) {
+            return Err(error(Some(*field), ErrorKind::DuplicateField));
+        }
+
+        parse_field(*field, value, draft).map_err(|kind| error(Some(*field), kind))?;
+    }
+
+    if drafts.is_empty() {
+        return Err(ParseError {
+            monkey: None,
+            field: None,
+            kind: ErrorKind::NoMonkeys,
         });
+    }
+
+    drafts.sort_by_key(|draft| draft.id);
+    let ids: Vec<u16> = drafts.iter().map(|draft| draft.id).collect();
+
+    drafts
+        .into_iter()
+        .map(|draft| {
+            let error = |field, kind| ParseError {
+                monkey: Some(draft.id),
+                field: Some(field),
+                kind,
+            };
+            let missing = |field| error(field, ErrorKind::MissingField);
+            let resolve = |field, target: Option<u16>| {
+                let target = target.ok_or_else(|| missing(field))?;
+                ids.binary_search(&target)
+                    .map_err(|_| error(field, ErrorKind::UnknownMonkey))
+            };
 
-        Some(Monkey {
-            inspected_items: 0,
-            items,
-            operation,
-            divisible_by,
-            test,
+            Ok(Monkey {
+                id: draft.id,
+                inspected_items: 0,
+                if_true: resolve(Field::IfTrue, draft.if_true)?,
+                if_false: resolve(Field::IfFalse, draft.if_false)?,
+                items: draft.items.ok_or_else(|| missing(Field::StartingItems))?,
+                operation: draft.operation.ok_or_else(|| missing(Field::Operation))?,
+                divisible_by: draft.divisible_by.ok_or_else(|| missing(Field::Test))?,
+            })
         })
+        .collect()
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SimulationError {
+    Parse(ParseError),
+    Eval { monkey: u16, error: EvalError },
+    // Part 2 can't keep worry levels small if this monkey's operation doesn't
+    // work modulo the divisors
+    NotModular(u16),
+    // The product of all the divisors doesn't fit in an i64
+    DivisorOverflow,
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::Parse(e) => write!(f, "invalid input: {e}"),
+            SimulationError::Eval { monkey, error } => write!(f, "monkey {monkey}: {error}"),
+            SimulationError::NotModular(monkey) => write!(
+                f,
+                "monkey {monkey}: operation doesn't commute with the modulo reduction"
+            ),
+            SimulationError::DivisorOverflow => write!(f, "product of the divisors overflows"),
+        }
     }
 }
 
-fn logic(monkeys: &mut Vec<Monkey>, rounds: i32, reducer: Box<dyn Fn(i64) -> i64>) -> i64 {
+impl Error for SimulationError {}
+
+impl From<ParseError> for SimulationError {
+    fn from(e: ParseError) -> Self {
+        SimulationError::Parse(e)
+    }
+}
+
+fn logic(
+    monkeys: &mut [Monkey],
+    rounds: i32,
+    reducer: Box<dyn Fn(i64) -> i64>,
+) -> Result<i64, SimulationError> {
     for _ in 0..rounds {
         for i in 0..monkeys.len() {
-            let items = monkeys[i].items.clone();
+            let items = std::mem::take(&mut monkeys[i].items);
 
             for item in items.iter() {
-                let worry_level = reducer((*monkeys[i].operation)(*item));
+                let worry_level =
+                    monkeys[i]
+                        .operation
+                        .eval(*item)
+                        .map_err(|error| SimulationError::Eval {
+                            monkey: monkeys[i].id,
+                            error,
+                        })?;
+                let worry_level = reducer(worry_level);
 
-                let monkey_id = (*monkeys[i].test)(worry_level);
-                monkeys[monkey_id as usize].items.push(worry_level)
+                let target = monkeys[i].target(worry_level);
+                monkeys[target].items.push(worry_level)
             }
 
             monkeys[i].inspected_items += items.len() as i64;
-            monkeys[i].items = Vec::new();
         }
     }
 
-    monkeys
+    Ok(monkeys
         .iter()
         .map(|monke| monke.inspected_items)
         .sorted()
         .rev()
         .take(2)
-        .product()
+        .product())
 }
 
 #[aoc(day11, part1)]
-pub fn part1(input: &str) -> i64 {
-    let mut monkeys: Vec<Monkey> = input
-        .lines()
-        .batching(|it| {
-            let lines = it.next_chunk::<6>().ok();
-            it.next();
-            lines
-        })
-        .map(|lines| Monkey::from_lines(lines).unwrap())
-        .collect();
+pub fn part1(input: &str) -> Result<i64, SimulationError> {
+    let mut monkeys = parse_monkeys(input)?;
 
     logic(&mut monkeys, 20, Box::new(|x| x / 3))
 }
 
 #[aoc(day11, part2)]
-pub fn part2(input: &str) -> i64 {
-    let mut monkeys: Vec<Monkey> = input
-        .lines()
-        .batching(|it| {
-            let lines = it.next_chunk::<6>().ok();
-            it.next();
-            lines
-        })
-        .map(|lines| Monkey::from_lines(lines).unwrap())
-        .collect();
+pub fn part2(input: &str) -> Result<i64, SimulationError> {
+    let mut monkeys = parse_monkeys(input)?;
 
-    let divider: i64 = monkeys.iter().map(|monkey| monkey.divisible_by).product();
+    if let Some(monkey) = monkeys
+        .iter()
+        .find(|monkey| !monkey.operation.commutes_with_mod())
+    {
+        return Err(SimulationError::NotModular(monkey.id));
+    }
+
+    let divider = monkeys
+        .iter()
+        .try_fold(1i64, |product, monkey| {
+            product.checked_mul(monkey.divisible_by)
+        })
+        .ok_or(SimulationError::DivisorOverflow)?;
     logic(&mut monkeys, 10000, Box::new(move |x| x % divider))
 }
 
@@ -133,13 +619,206 @@ pub fn part2(input: &str) -> i64 {
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day11::{part1, part2};
+    use crate::day11::{
+        ErrorKind, EvalError, Expr, Field, Op, ParseError, SimulationError, parse_monkeys, part1,
+        part2,
+    };
 
     #[test]
     fn test_day11() {
         let input = &read_to_string("input/2022/day11.txt").unwrap();
 
-        assert_eq!(61503, part1(input));
-        assert_eq!(14081365540, part2(input));
+        assert_eq!(Ok(61503), part1(input));
+        assert_eq!(Ok(14081365540), part2(input));
+        assert_eq!(Ok(61503), part1(input.trim_end()));
+    }
+
+    #[test]
+    fn test_day11_parser() {
+        let example = "\
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+Monkey 1:
+Starting items: 54,65,  75, 74
+Operation: new=old+6
+Test:   divisible   by 19
+If false: throw to monkey 0
+If true: throw to monkey 2
+
+monkey 0:
+\tstarting items: 79, 98
+\toperation: new = old * 19
+\ttest: divisible by 23
+\tif true: throw to monkey 2
+\tif false: throw to monkey 3
+Monkey 2 :
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3";
+
+        assert_eq!(Ok(10605), part1(example));
+        assert_eq!(Ok(2713310158), part2(example));
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            parse_monkeys(example)
+                .unwrap()
+                .iter()
+                .map(|monkey| monkey.id)
+                .collect::<Vec<_>>()
+        );
+
+        let monkeys = parse_monkeys(
+            "Monkey 7:\nStarting items:\nOperation: new = old / 2\nTest: divisible by 2\n\
+             If true: throw to monkey 9\nIf false: throw to monkey 9\n\
+             Monkey 9:\nStarting items: 1\nOperation: new = 100 - old\nTest: divisible by 3\n\
+             If true: throw to monkey 7\nIf false: throw to monkey 7",
+        )
+        .unwrap();
+        assert!(monkeys[0].items.is_empty());
+        assert_eq!(Ok(5), monkeys[0].operation.eval(10));
+        assert_eq!(Ok(90), monkeys[1].operation.eval(10));
+        assert_eq!((1, 1), (monkeys[0].if_true, monkeys[0].if_false));
+
+        let error = |input: &str| parse_monkeys(input).err().unwrap();
+        let broken = example.replace("old * old", "old % old");
+        assert_eq!(
+            ParseError {
+                monkey: Some(2),
+                field: Some(Field::Operation),
+                kind: ErrorKind::UnknownOperator,
+            },
+            error(&broken)
+        );
+        assert_eq!(
+            ParseError {
+                monkey: Some(1),
+                field: Some(Field::IfTrue),
+                kind: ErrorKind::UnknownMonkey,
+            },
+            error(&example.replace("If true: throw to monkey 2", "If true: throw to monkey 5"))
+        );
+        assert_eq!(
+            ParseError {
+                monkey: Some(3),
+                field: Some(Field::Test),
+                kind: ErrorKind::MissingField,
+            },
+            error(&example.replace("  Test: divisible by 17\n", ""))
+        );
+        assert_eq!(
+            ParseError {
+                monkey: Some(0),
+                field: Some(Field::StartingItems),
+                kind: ErrorKind::InvalidNumber,
+            },
+            error(&example.replace("79, 98", "79, x"))
+        );
+        assert_eq!(
+            ErrorKind::DuplicateMonkey,
+            error(&example.replace("Monkey 2 :", "Monkey 3:")).kind
+        );
+        assert_eq!(
+            ErrorKind::DivisionByZero,
+            error(&example.replace("old + 3", "old / 0")).kind
+        );
+        assert_eq!(
+            ErrorKind::FieldOutsideMonkey,
+            error("Test: divisible by 3\nMonkey 0:").kind
+        );
+        assert_eq!(ErrorKind::NoMonkeys, error("\n\n").kind);
+        assert_eq!(
+            ParseError {
+                monkey: None,
+                field: None,
+                kind: ErrorKind::InvalidHeader,
+            },
+            error(&example.replace("Monkey 2 :", "Monkey x:"))
+        );
+    }
+
+    #[test]
+    fn test_day11_expr() {
+        let expr: Expr = "(old + 3) * (old - 1) - 2 * old".parse().unwrap();
+        assert_eq!("(old + 3) * (old - 1) - 2 * old", expr.to_string());
+        assert_eq!(Ok(22), expr.eval(5));
+        assert_eq!(Ok(expr.clone()), expr.to_string().parse::<Expr>());
+
+        let nested: Expr = "old - (old - 1) / (2 / old)".parse().unwrap();
+        assert_eq!("old - (old - 1) / (2 / old)", nested.to_string());
+        assert_eq!(Ok(nested.clone()), nested.to_string().parse());
+        assert_eq!(Ok(6), "old*-3".parse::<Expr>().unwrap().eval(-2));
+
+        let square: Expr = "old * old".parse().unwrap();
+        assert_eq!(
+            Expr::BinOp(Box::new(Expr::Old), Op::Mul, Box::new(Expr::Old)),
+            square
+        );
+        assert_eq!(
+            Err(EvalError::Overflow(i64::MAX, Op::Mul, i64::MAX)),
+            square.eval(i64::MAX)
+        );
+        assert_eq!(
+            Err(EvalError::DivisionByZero),
+            "1 / old".parse::<Expr>().unwrap().eval(0)
+        );
+        assert_eq!(
+            r#"{"BinOp":["Old","Mul",{"Const":19}]}"#,
+            serde_json::to_string(&"old * 19".parse::<Expr>().unwrap()).unwrap()
+        );
+
+        assert_eq!(Err(ErrorKind::InvalidOperation), "old +".parse::<Expr>());
+        assert_eq!(Err(ErrorKind::InvalidOperation), "(old".parse::<Expr>());
+        assert_eq!(Err(ErrorKind::UnknownOperator), "old ^ 2".parse::<Expr>());
+
+        let modulus = 7 * 11 * 13;
+        for (text, commutes) in [
+            ("old * old + 3", true),
+            ("old - 100", true),
+            ("old * (10 / 5)", true),
+            ("old / 2", false),
+            ("(old + 1) / 3", false),
+        ] {
+            let expr: Expr = text.parse().unwrap();
+            assert_eq!(commutes, expr.commutes_with_mod(), "{text}");
+
+            if commutes {
+                for old in 0..2000 {
+                    let reduced = expr.eval(old % modulus).unwrap();
+                    assert_eq!(0, (reduced - expr.eval(old).unwrap()) % modulus);
+                }
+            }
+        }
+
+        let input = &read_to_string("input/2022/day11.txt").unwrap();
+        assert_eq!(
+            Err(SimulationError::NotModular(5)),
+            part2(&input.replace("old + 8", "old / 8"))
+        );
+
+        let overflowing = "Monkey 0:\nStarting items: 4000000000\nOperation: new = old * old\n\
+                           Test: divisible by 2\nIf true: throw to monkey 0\nIf false: throw to monkey 0";
+        assert_eq!(
+            Err(SimulationError::Eval {
+                monkey: 0,
+                error: EvalError::Overflow(4000000000, Op::Mul, 4000000000),
+            }),
+            part1(overflowing)
+        );
+
+        let many = (0..12)
+            .map(|id| {
+                format!(
+                    "Monkey {id}:\nStarting items: 1\nOperation: new = old + 1\n\
+                     Test: divisible by 97\nIf true: throw to monkey 0\nIf false: throw to monkey 0\n"
+                )
+            })
+            .collect::<String>();
+        assert_eq!(Err(SimulationError::DivisorOverflow), part2(&many));
     }
 }
@@ -1,4 +1,12 @@
-use pathfinding::prelude::astar;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, VecDeque},
+    error::Error,
+    fmt, thread,
+    time::Duration,
+};
+
+use crate::grid::{ALL_DIRECTIONS, Direction, Grid, ORTHOGONAL, Point};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 enum Tile {
@@ -8,12 +16,12 @@ enum Tile {
 }
 
 impl Tile {
-    fn from_char(c: char) -> Tile {
+    fn from_char(c: char) -> Option<Tile> {
         match c {
-            'S' => Tile::Start,
-            'E' => Tile::End,
-            'a'..='z' => Tile::Tile(c as u32 - 97),
-            _ => unreachable!(),
+            'S' => Some(Tile::Start),
+            'E' => Some(Tile::End),
+            'a'..='z' => Some(Tile::Tile(c as u32 - 97)),
+            _ => None,
         }
     }
 
@@ -36,44 +44,64 @@ impl Tile {
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct Pos {
-    x: u32,
-    y: u32,
+    x: usize,
+    y: usize,
     tile: Tile,
 }
 
-impl Pos {
-    fn distance(&self, other: &Pos) -> u32 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
-    }
+#[derive(Clone, Copy, Debug)]
+pub enum StepCost {
+    Uniform,
+    // Called with the height of the destination minus the height of the source,
+    // anything below 1 is rounded up so routes always make progress
+    ByDelta(fn(i64) -> usize),
+}
 
-    fn move_choices(&self, map: &Map) -> Vec<(Pos, u32)> {
-        let mut moves: Vec<Pos> = Vec::new();
+#[derive(Clone, Copy, Debug)]
+pub struct ClimbRules {
+    pub max_ascent: u32,
+    pub max_descent: u32,
+    pub diagonal: bool,
+    pub step_cost: StepCost,
+}
 
-        if self.y + 1 < map.0.len() as u32 && self.can_move_to(map.get(self.y + 1, self.x)) {
-            moves.push(map.get(self.y + 1, self.x).clone())
+impl Default for ClimbRules {
+    fn default() -> Self {
+        Self {
+            max_ascent: 1,
+            max_descent: u32::MAX,
+            diagonal: false,
+            step_cost: StepCost::Uniform,
         }
+    }
+}
 
-        if self.x + 1 < map.0[self.y as usize].len() as u32
-            && self.can_move_to(map.get(self.y, self.x + 1))
-        {
-            moves.push(map.get(self.y, self.x + 1).clone())
-        }
+impl ClimbRules {
+    fn can_move(&self, from: &Pos, to: &Pos) -> bool {
+        let (from, to) = (from.tile.get_height(), to.tile.get_height());
 
-        if self.y > 0 && self.can_move_to(map.get(self.y - 1, self.x)) {
-            moves.push(map.get(self.y - 1, self.x).clone())
+        if to >= from {
+            to - from <= self.max_ascent
+        } else {
+            from - to <= self.max_descent
         }
+    }
 
-        if self.x > 0 && self.can_move_to(map.get(self.y, self.x - 1)) {
-            moves.push(map.get(self.y, self.x - 1).clone())
+    fn cost(&self, from: &Pos, to: &Pos) -> usize {
+        match self.step_cost {
+            StepCost::Uniform => 1,
+            StepCost::ByDelta(cost) => {
+                cost(to.tile.get_height() as i64 - from.tile.get_height() as i64).max(1)
+            }
         }
-
-        moves.into_iter().map(|p| (p, 1)).collect()
     }
 
-    fn can_move_to(&self, other: &Pos) -> bool {
-        self.tile.get_height() >= other.tile.get_height()
-            || (other.tile.get_height() > 0
-                && self.tile.get_height() == other.tile.get_height() - 1)
+    fn directions(&self) -> &'static [Direction] {
+        if self.diagonal {
+            &ALL_DIRECTIONS
+        } else {
+            &ORTHOGONAL
+        }
     }
 }
 
@@ -82,131 +110,572 @@ pub struct Input {
     map: Map,
     start: Pos,
     end: Pos,
+    rules: ClimbRules,
 }
 
-#[derive(Debug)]
-struct Map(Vec<Vec<Pos>>);
+#[derive(Debug, Eq, PartialEq)]
+pub enum MapError {
+    // Lines and columns both start from 1
+    InvalidTile { line: usize, column: usize, c: char },
+    RaggedRows,
+    MissingStart,
+    MissingEnd,
+    MultipleStarts,
+    MultipleEnds,
+}
 
-impl Map {
-    fn get(&self, y: u32, x: u32) -> &Pos {
-        &self.0[y as usize][x as usize]
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::InvalidTile { line, column, c } => {
+                write!(f, "invalid tile `{c}` at {line}:{column}")
+            }
+            MapError::RaggedRows => write!(f, "rows have different lengths"),
+            MapError::MissingStart => write!(f, "no start tile"),
+            MapError::MissingEnd => write!(f, "no end tile"),
+            MapError::MultipleStarts => write!(f, "more than one start tile"),
+            MapError::MultipleEnds => write!(f, "more than one end tile"),
+        }
     }
+}
 
-    fn print(&self, current: &Pos, highlight: &[Pos]) {
-        for row in &self.0 {
-            for pos in row {
-                if pos.x == current.x && pos.y == current.y {
-                    print!("\x1b[93mX\x1b[0m")
-                } else if highlight
-                    .iter()
-                    .any(|high| pos.x == high.x && pos.y == high.y)
-                {
-                    print!("\x1b[94m{}\x1b[0m", pos.tile.to_char());
-                } else {
-                    print!("{}", pos.tile.to_char());
-                }
+impl Error for MapError {}
+
+impl Input {
+    // Heights go from `a` to `z`, with exactly one `S` and one `E`
+    pub fn parse(input: &str) -> Result<Input, MapError> {
+        let rows = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(x, c)| {
+                        Tile::from_char(c).ok_or(MapError::InvalidTile {
+                            line: y + 1,
+                            column: x + 1,
+                            c,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let map = Grid::from_rows(rows)
+            .ok_or(MapError::RaggedRows)?
+            .map(|(x, y), tile| Pos { x, y, tile: *tile });
+
+        let find = |tile, missing, multiple| {
+            let mut found = map.iter().filter(|(_, pos)| pos.tile == tile);
+            let (_, pos) = found.next().ok_or(missing)?;
+            match found.next() {
+                Some(_) => Err(multiple),
+                None => Ok(pos.clone()),
             }
+        };
+        let start = find(
+            Tile::Start,
+            MapError::MissingStart,
+            MapError::MultipleStarts,
+        )?;
+        let end = find(Tile::End, MapError::MissingEnd, MapError::MultipleEnds)?;
 
-            println!()
-        }
+        Ok(Input {
+            map,
+            start,
+            end,
+            rules: ClimbRules::default(),
+        })
+    }
+
+    pub fn with_rules(self, rules: ClimbRules) -> Input {
+        Input { rules, ..self }
+    }
 
-        println!()
+    fn neighbours(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.rules
+            .directions()
+            .iter()
+            .filter_map(move |direction| self.map.offset(point, *direction))
+    }
+
+    fn moves_from(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbours(point)
+            .filter(move |next| self.rules.can_move(&self.map[point], &self.map[*next]))
+    }
+
+    fn moves_into(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbours(point)
+            .filter(move |previous| self.rules.can_move(&self.map[*previous], &self.map[point]))
     }
 }
 
+type Map = Grid<Pos>;
+
 #[aoc_generator(day12)]
 fn input_generator(input: &str) -> Input {
-    let mut start = Pos {
-        x: 0,
-        y: 0,
-        tile: Tile::Start,
-    };
+    Input::parse(input).unwrap_or_else(|e| panic!("{e}"))
+}
 
-    let mut end = Pos {
-        x: 0,
-        y: 0,
-        tile: Tile::End,
-    };
+// Searches backwards from the end, so a single pass gives the cost of the
+// cheapest climb from every tile that can reach it. Uniform costs only need
+// a breadth-first search, anything else falls back to Dijkstra
+pub fn distances_to_end(input: &Input) -> Grid<Option<usize>> {
+    match input.rules.step_cost {
+        StepCost::Uniform => bfs_to_end(input),
+        StepCost::ByDelta(_) => dijkstra_to_end(input),
+    }
+}
+
+fn bfs_to_end(input: &Input) -> Grid<Option<usize>> {
+    let map = &input.map;
+    let mut distances = Grid::filled(map.width(), map.height(), None);
+    let mut queue = VecDeque::from([((input.end.x, input.end.y), 0)]);
+    distances[(input.end.x, input.end.y)] = Some(0);
+
+    while let Some((point, distance)) = queue.pop_front() {
+        for neighbour in input.neighbours(point) {
+            if distances[neighbour].is_none() && input.rules.can_move(&map[neighbour], &map[point])
+            {
+                distances[neighbour] = Some(distance + 1);
+                queue.push_back((neighbour, distance + 1));
+            }
+        }
+    }
+
+    distances
+}
+
+fn dijkstra_to_end(input: &Input) -> Grid<Option<usize>> {
+    let map = &input.map;
+    let mut distances: Grid<Option<usize>> = Grid::filled(map.width(), map.height(), None);
+    let mut heap = BinaryHeap::from([Reverse((0, (input.end.x, input.end.y)))]);
+    distances[(input.end.x, input.end.y)] = Some(0);
+
+    while let Some(Reverse((distance, point))) = heap.pop() {
+        if distances[point].is_some_and(|best| best < distance) {
+            continue;
+        }
+
+        for neighbour in input.neighbours(point) {
+            if !input.rules.can_move(&map[neighbour], &map[point]) {
+                continue;
+            }
+
+            let candidate = distance + input.rules.cost(&map[neighbour], &map[point]);
+            if distances[neighbour].is_none_or(|best| candidate < best) {
+                distances[neighbour] = Some(candidate);
+                heap.push(Reverse((candidate, neighbour)));
+            }
+        }
+    }
+
+    distances
+}
+
+pub fn route(input: &Input, from: Point) -> Option<Vec<Point>> {
+    let map = &input.map;
+    let distances = distances_to_end(input);
+    let mut distance = distances[from]?;
+    let mut route = vec![from];
+
+    while distance > 0 {
+        let current = *route.last().unwrap();
+        let next = input.neighbours(current).find(|neighbour| {
+            input.rules.can_move(&map[current], &map[*neighbour])
+                && distances[*neighbour]
+                    == distance.checked_sub(input.rules.cost(&map[current], &map[*neighbour]))
+        })?;
+
+        route.push(next);
+        distance = distances[next]?;
+    }
+
+    Some(route)
+}
+
+pub fn shortest_route(input: &Input) -> Option<Vec<Point>> {
+    route(input, (input.start.x, input.start.y))
+}
+
+fn arrow(from: Point, to: Point) -> char {
+    match (to.0.cmp(&from.0), to.1.cmp(&from.1)) {
+        (Ordering::Equal, Ordering::Less) => '^',
+        (Ordering::Greater, Ordering::Equal) => '>',
+        (Ordering::Equal, Ordering::Greater) => 'v',
+        (Ordering::Less, Ordering::Equal) => '<',
+        (Ordering::Greater, Ordering::Less) => '↗',
+        (Ordering::Greater, Ordering::Greater) => '↘',
+        (Ordering::Less, Ordering::Greater) => '↙',
+        _ => '↖',
+    }
+}
+
+pub fn render_route(input: &Input, route: &[Point]) -> String {
+    let mut arrows = Grid::filled(input.map.width(), input.map.height(), '.');
+
+    for step in route.windows(2) {
+        arrows[step[0]] = arrow(step[0], step[1]);
+    }
+
+    if let Some(last) = route.last() {
+        arrows[*last] = 'E';
+    }
+
+    arrows.render(|c| *c)
+}
+
+// One frame per BFS layer, the current frontier in yellow and everything
+// already explored in blue
+pub fn search_frames(input: &Input) -> Vec<String> {
+    let distances = distances_to_end(input);
+    let layers = distances.iter().filter_map(|(_, d)| *d).max().unwrap_or(0);
+
+    (0..=layers)
+        .map(|layer| {
+            input.map.render_ansi(|point, pos| match distances[point] {
+                Some(d) if d == layer => (pos.tile.to_char(), Some(93)),
+                Some(d) if d < layer => (pos.tile.to_char(), Some(94)),
+                _ => (pos.tile.to_char(), None),
+            })
+        })
+        .collect()
+}
+
+pub fn animate(input: &Input, delay: Duration) {
+    for frame in search_frames(input) {
+        print!("\x1b[2J\x1b[H{frame}");
+        thread::sleep(delay);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cliff {
+    pub from: Point,
+    pub to: Point,
+    pub from_height: u32,
+    pub to_height: u32,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Blocked {
+    pub start: Point,
+    pub region: usize,
+    pub reachable_tiles: usize,
+    pub cliffs: Vec<Cliff>,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} can only reach {} tiles, every way out is a cliff:",
+            self.start, self.reachable_tiles
+        )?;
+
+        for cliff in &self.cliffs {
+            write!(
+                f,
+                " {:?} ({}) -> {:?} ({})",
+                cliff.from, cliff.from_height, cliff.to, cliff.to_height
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Reachability {
+    pub regions: Grid<usize>,
+    pub region_count: usize,
+    pub blocked: Vec<Blocked>,
+}
+
+// Kosaraju's algorithm, first pass collects tiles by DFS finishing time and
+// the second one peels off regions by walking the moves backwards
+fn regions(input: &Input) -> (Grid<usize>, usize) {
+    let map = &input.map;
+    let mut visited = Grid::filled(map.width(), map.height(), false);
+    let mut order = Vec::new();
+
+    for root in map.points() {
+        if visited[root] {
+            continue;
+        }
+
+        visited[root] = true;
+        let mut stack = vec![(root, input.moves_from(root).collect::<Vec<_>>())];
+
+        while let Some((point, pending)) = stack.last_mut() {
+            match pending.pop() {
+                Some(next) if !visited[next] => {
+                    visited[next] = true;
+                    stack.push((next, input.moves_from(next).collect()));
+                }
+                Some(_) => {}
+                None => {
+                    order.push(*point);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut regions = Grid::filled(map.width(), map.height(), usize::MAX);
+    let mut region_count = 0;
 
-    let map = Map(input
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, c)| {
-                    let pos = Pos {
-                        x: x as u32,
-                        y: y as u32,
-                        tile: Tile::from_char(c),
-                    };
-                    match pos.tile {
-                        Tile::End => {
-                            end.x = pos.x;
-                            end.y = pos.y;
-                        }
-                        Tile::Start => {
-                            start.x = pos.x;
-                            start.y = pos.y;
-                        }
-                        _ => {}
-                    }
-
-                    pos
+    for root in order.into_iter().rev() {
+        if regions[root] != usize::MAX {
+            continue;
+        }
+
+        regions[root] = region_count;
+        let mut stack = vec![root];
+
+        while let Some(point) = stack.pop() {
+            for previous in input.moves_into(point) {
+                if regions[previous] == usize::MAX {
+                    regions[previous] = region_count;
+                    stack.push(previous);
+                }
+            }
+        }
+
+        region_count += 1;
+    }
+
+    (regions, region_count)
+}
+
+fn reachable_from(input: &Input, from: Point) -> Grid<bool> {
+    let mut reachable = Grid::filled(input.map.width(), input.map.height(), false);
+    let mut stack = vec![from];
+    reachable[from] = true;
+
+    while let Some(point) = stack.pop() {
+        for next in input.moves_from(point) {
+            if !reachable[next] {
+                reachable[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    reachable
+}
+
+// Every edge leaving the reachable area has to be a move the rules forbid,
+// otherwise the tile on the other side would have been reached too
+fn explain(input: &Input, start: Point, region: usize) -> Blocked {
+    let map = &input.map;
+    let reachable = reachable_from(input, start);
+
+    let cliffs = reachable
+        .iter()
+        .filter(|(_, reached)| **reached)
+        .flat_map(|(from, _)| {
+            input
+                .neighbours(from)
+                .filter(|to| !reachable[*to])
+                .map(move |to| Cliff {
+                    from,
+                    to,
+                    from_height: map[from].tile.get_height(),
+                    to_height: map[to].tile.get_height(),
                 })
-                .collect()
         })
-        .collect());
+        .collect();
 
-    Input { map, start, end }
+    Blocked {
+        start,
+        region,
+        reachable_tiles: reachable.iter().filter(|(_, reached)| **reached).count(),
+        cliffs,
+    }
 }
 
+pub fn analyse(input: &Input) -> Reachability {
+    let (regions, region_count) = regions(input);
+    let distances = distances_to_end(input);
+    let mut explained: HashMap<usize, Blocked> = HashMap::new();
+
+    let blocked = input
+        .map
+        .iter()
+        .filter(|(point, pos)| pos.tile.get_height() == 0 && distances[*point].is_none())
+        .map(|(start, _)| {
+            let region = regions[start];
+            let template = explained
+                .entry(region)
+                .or_insert_with(|| explain(input, start, region));
+
+            // Tiles sharing a region can reach exactly the same tiles
+            Blocked {
+                start,
+                region,
+                reachable_tiles: template.reachable_tiles,
+                cliffs: template.cliffs.clone(),
+            }
+        })
+        .collect();
+
+    Reachability {
+        regions,
+        region_count,
+        blocked,
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ClimbError {
+    StartIsolated(Point),
+    EndUnreachable(Point),
+    NoLowPointReachesEnd,
+}
+
+impl fmt::Display for ClimbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClimbError::StartIsolated(start) => write!(f, "start {start:?} has no legal moves"),
+            ClimbError::EndUnreachable(start) => {
+                write!(f, "end can't be reached from start {start:?}")
+            }
+            ClimbError::NoLowPointReachesEnd => write!(f, "no lowest tile can reach the end"),
+        }
+    }
+}
+
+impl Error for ClimbError {}
+
 #[aoc(day12, part1)]
-pub fn part1(input: &Input) -> u32 {
-    astar(
-        &input.start,
-        |p| p.move_choices(&input.map),
-        |p| p.distance(&input.end),
-        |p| p.tile == Tile::End,
-    )
-    .expect("Cant find a path")
-    .1
+pub fn part1(input: &Input) -> Result<usize, ClimbError> {
+    let start = (input.start.x, input.start.y);
+
+    distances_to_end(input)[start].ok_or_else(|| {
+        if input.moves_from(start).next().is_none() {
+            ClimbError::StartIsolated(start)
+        } else {
+            ClimbError::EndUnreachable(start)
+        }
+    })
 }
 
 #[aoc(day12, part2)]
-pub fn part2(input: &Input) -> u32 {
+pub fn part2(input: &Input) -> Result<usize, ClimbError> {
+    let distances = distances_to_end(input);
+
     input
         .map
-        .0
         .iter()
-        .flatten()
-        .filter(|pos| pos.tile.get_height() == 0)
-        .filter_map(|pos| {
-            Some(
-                astar(
-                    pos,
-                    |p| p.move_choices(&input.map),
-                    |p| p.distance(&input.end),
-                    |p| p.tile == Tile::End,
-                )?
-                .1,
-            )
-        })
+        .filter(|(_, pos)| pos.tile.get_height() == 0)
+        .filter_map(|(point, _)| distances[point])
         .min()
-        .unwrap()
+        .ok_or(ClimbError::NoLowPointReachesEnd)
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day12::{input_generator, part1, part2};
+    use crate::day12::{
+        ClimbError, ClimbRules, Input, MapError, StepCost, analyse, distances_to_end,
+        input_generator, part1, part2, render_route, search_frames, shortest_route,
+    };
 
     #[test]
     fn test_day12() {
         let input = input_generator(&read_to_string("input/2022/day12.txt").unwrap());
 
-        assert_eq!(352, part1(&input));
-        assert_eq!(345, part2(&input));
+        assert_eq!(352, part1(&input).unwrap());
+        assert_eq!(345, part2(&input).unwrap());
+
+        let example = input_generator("Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi");
+        assert_eq!(31, part1(&example).unwrap());
+        assert_eq!(29, part2(&example).unwrap());
+
+        let distances = distances_to_end(&example);
+        assert_eq!(Some(0), distances[(5, 2)]);
+        assert_eq!(Some(30), distances[(1, 0)]);
+
+        let route = shortest_route(&example).unwrap();
+        assert_eq!(32, route.len());
+        assert_eq!(
+            ">>vv<<<<\n..vvv<<^\n..vv>E^^\n..v>>>^^\n..>>>>>^\n",
+            render_route(&example, &route)
+        );
+        assert_eq!(32, search_frames(&example).len());
+    }
+
+    #[test]
+    fn test_day12_rules() {
+        let example = || input_generator("Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi");
+
+        let diagonal = example().with_rules(ClimbRules {
+            diagonal: true,
+            ..ClimbRules::default()
+        });
+        assert!(part1(&diagonal).unwrap() < 31);
+
+        let flat = example().with_rules(ClimbRules {
+            max_ascent: 0,
+            ..ClimbRules::default()
+        });
+        assert_eq!(None, distances_to_end(&flat)[(0, 0)]);
+        assert_eq!(Some(1), distances_to_end(&flat)[(4, 2)]);
+
+        let weighted = example().with_rules(ClimbRules {
+            step_cost: StepCost::ByDelta(|delta| if delta > 0 { 5 } else { 1 }),
+            ..ClimbRules::default()
+        });
+        let route = shortest_route(&weighted).unwrap();
+        assert_eq!(31 + 4 * 25, part1(&weighted).unwrap());
+        assert_eq!(32, route.len());
+    }
+
+    #[test]
+    fn test_day12_reachability() {
+        let input = input_generator(&read_to_string("input/2022/day12.txt").unwrap());
+        let reachability = analyse(&input);
+        assert!(!reachability.blocked.is_empty());
+        assert!(reachability.blocked.iter().all(|blocked| {
+            !blocked.cliffs.is_empty()
+                && blocked
+                    .cliffs
+                    .iter()
+                    .all(|cliff| cliff.to_height > cliff.from_height + 1)
+        }));
+
+        let example = input_generator("Sbcz\nbbcE");
+        let reachability = analyse(&example);
+        assert_eq!(Err(ClimbError::EndUnreachable((0, 0))), part1(&example));
+        assert_eq!(2, reachability.region_count);
+        assert_eq!(reachability.regions[(0, 0)], reachability.regions[(2, 1)]);
+        assert_eq!(1, reachability.blocked.len());
+        assert_eq!(
+            "(0, 0) can only reach 6 tiles, every way out is a cliff: (2, 0) (2) -> (3, 0) (25) (2, 1) (2) -> (3, 1) (25)",
+            reachability.blocked[0].to_string()
+        );
+
+        assert_eq!(
+            MapError::InvalidTile {
+                line: 2,
+                column: 3,
+                c: '#',
+            },
+            Input::parse("Sab\nab#E").unwrap_err()
+        );
+        assert_eq!(MapError::RaggedRows, Input::parse("Sab\naE").unwrap_err());
+        assert_eq!(MapError::MissingEnd, Input::parse("Sab").unwrap_err());
+        assert_eq!(
+            MapError::MultipleStarts,
+            Input::parse("SaS\nabE").unwrap_err()
+        );
+
+        let isolated = input_generator("Sc\ncE");
+        assert_eq!(Err(ClimbError::StartIsolated((0, 0))), part1(&isolated));
+        assert_eq!(Err(ClimbError::NoLowPointReachesEnd), part2(&isolated));
+        assert_eq!(2, analyse(&isolated).blocked[0].cliffs.len());
     }
 }
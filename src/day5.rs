@@ -1,36 +1,19 @@
-use std::str::FromStr;
+use std::{error::Error, fmt, slice, str::FromStr};
 
 use itertools::Itertools;
-use regex::Regex;
 
-lazy_static! {
-    static ref MOVE_PARSE_REGEX: regex::Regex =
-        Regex::new("move ([0-9]+) from ([0-9]+) to ([0-9]+)").unwrap();
-    static ref LAST_NUM_REGEX: regex::Regex = Regex::new("([0-9]) $").unwrap();
-    static ref CRATES_FIND_REGEX: regex::Regex = Regex::new(r"(?:\[([A-Z])\]|   )(?: |$)").unwrap();
-}
-
-#[derive(Clone)]
-struct Row(Vec<char>);
+// The top of the stack is the last element, so moving crates only touches
+// the end of both vectors
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Row(Vec<String>);
 
 impl Row {
-    fn prepend_same_order(&mut self, letters: Vec<char>) {
-        self.0 = letters
-            .into_iter()
-            .chain(self.0.clone().into_iter())
-            .collect();
+    fn take(&mut self, amount: usize) -> Vec<String> {
+        self.0.split_off(self.0.len() - amount)
     }
 
-    fn prepend(&mut self, letters: Vec<char>) {
-        self.0 = letters
-            .into_iter()
-            .rev()
-            .chain(self.0.clone().into_iter())
-            .collect();
-    }
-
-    fn take(&mut self, amount: usize) -> Vec<char> {
-        (0..amount).map(|_| self.0.remove(0)).rev().collect()
+    fn put(&mut self, letters: impl IntoIterator<Item = String>) {
+        self.0.extend(letters);
     }
 
     fn new() -> Self {
@@ -38,93 +21,668 @@ impl Row {
     }
 }
 
-struct Move {
-    amount: usize,
-    cur_pos: usize,
-    new_pos: usize,
+#[derive(Debug, Eq, PartialEq)]
+pub enum MoveError {
+    NoSuchStack(usize),
+    NotEnoughCrates {
+        stack: usize,
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack(stack) => write!(f, "stack {stack} doesn't exist"),
+            MoveError::NotEnoughCrates {
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "can't take {requested} crates from stack {stack}, it only has {available}"
+            ),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    MissingSeparator,
+    MissingStackNumbers,
+    BadStackNumber,
+    UnexpectedChar(char),
+    UnclosedCrate,
+    EmptyLabel,
+    CrateOutsideStack,
+    ExpectedKeyword(&'static str),
+    InvalidNumber,
+    TrailingInput,
+}
+
+// Lines and columns both start from 1
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {:?}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SimulationError {
+    Parse(ParseError),
+    Move(MoveError),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::Parse(e) => write!(f, "invalid input at {e}"),
+            SimulationError::Move(e) => write!(f, "invalid move: {e}"),
+        }
+    }
+}
+
+impl Error for SimulationError {}
+
+impl From<ParseError> for SimulationError {
+    fn from(e: ParseError) -> Self {
+        SimulationError::Parse(e)
+    }
 }
 
-impl Move {
-    fn execute_same_order(&self, modify: &mut [Row]) {
-        let letters = modify[self.cur_pos - 1].take(self.amount);
-        modify[self.new_pos - 1].prepend_same_order(letters);
+impl From<MoveError> for SimulationError {
+    fn from(e: MoveError) -> Self {
+        SimulationError::Move(e)
     }
+}
+
+// Splits a line into runs of non-space characters along with the column they start at
+fn tokens(line: &str) -> Vec<(usize, String)> {
+    let mut tokens: Vec<(usize, String)> = Vec::new();
+    let mut previous = ' ';
 
-    fn execute(&self, modify: &mut [Row]) {
-        let letters = modify[self.cur_pos - 1].take(self.amount);
-        modify[self.new_pos - 1].prepend(letters);
+    for (column, c) in line.chars().enumerate() {
+        match (previous.is_whitespace(), c.is_whitespace()) {
+            (_, true) => {}
+            (true, false) => tokens.push((column, c.to_string())),
+            (false, false) => tokens.last_mut().unwrap().1.push(c),
+        }
+        previous = c;
     }
+
+    tokens
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Move {
+    pub amount: usize,
+    pub cur_pos: usize,
+    pub new_pos: usize,
 }
 
+// Keywords have to match exactly, `None` stands for a number
+const MOVE_PATTERN: [Option<&str>; 6] = [Some("move"), None, Some("from"), None, Some("to"), None];
+
 impl FromStr for Move {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let captures = MOVE_PARSE_REGEX.captures(s).unwrap();
+        let error = |column, kind| ParseError {
+            line: 1,
+            column: column + 1,
+            kind,
+        };
+        let end = s.chars().count();
+        let mut tokens = tokens(s).into_iter();
+        let mut numbers = Vec::with_capacity(3);
+
+        for expected in MOVE_PATTERN {
+            let (column, token) = tokens.next().unwrap_or((end, String::new()));
+
+            match expected {
+                Some(keyword) if token != keyword => {
+                    return Err(error(column, ParseErrorKind::ExpectedKeyword(keyword)));
+                }
+                Some(_) => {}
+                None => numbers.push(
+                    token
+                        .parse()
+                        .map_err(|_| error(column, ParseErrorKind::InvalidNumber))?,
+                ),
+            }
+        }
+
+        if let Some((column, _)) = tokens.next() {
+            return Err(error(column, ParseErrorKind::TrailingInput));
+        }
 
         Ok(Move {
-            amount: captures.get(1).unwrap().as_str().parse().unwrap(),
-            cur_pos: captures.get(2).unwrap().as_str().parse().unwrap(),
-            new_pos: captures.get(3).unwrap().as_str().parse().unwrap(),
+            amount: numbers[0],
+            cur_pos: numbers[1],
+            new_pos: numbers[2],
         })
     }
 }
 
-fn logic(input: &str, execute_move: &dyn Fn(&Move, &mut [Row])) -> String {
-    let (crates, moves) = input
-        .split("\n\n")
-        .map(String::from)
-        .collect_tuple::<(String, String)>()
-        .unwrap();
+// Stacks are numbered from 1 like in the drawing. Moves are validated before
+// they reach the crane, so lifting and placing only fail on crane bugs
+pub trait Crane {
+    // Returns how many crane operations the move took
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError>;
+
+    fn cost_per_operation(&self) -> usize {
+        1
+    }
+}
+
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError> {
+        for _ in 0..mov.amount {
+            let letters = yard.lift(mov.cur_pos, 1)?;
+            yard.place(mov.new_pos, letters)?;
+        }
+
+        Ok(mov.amount)
+    }
+}
+
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError> {
+        let letters = yard.lift(mov.cur_pos, mov.amount)?;
+        yard.place(mov.new_pos, letters)?;
+
+        Ok(1)
+    }
+}
+
+// Moves several crates at once like the 9001, but only up to its capacity per lift
+pub struct LimitedCrane {
+    pub capacity: usize,
+}
+
+impl Crane for LimitedCrane {
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError> {
+        let mut left = mov.amount;
+        let mut operations = 0;
+
+        while left > 0 {
+            let lift = left.min(self.capacity.max(1));
+            let letters = yard.lift(mov.cur_pos, lift)?;
+            yard.place(mov.new_pos, letters)?;
+
+            left -= lift;
+            operations += 1;
+        }
+
+        Ok(operations)
+    }
+}
+
+// Pulls crates out from under the stack in one go and puts them on top
+pub struct BottomCrane;
+
+impl Crane for BottomCrane {
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError> {
+        let letters = yard.lift_bottom(mov.cur_pos, mov.amount)?;
+        yard.place(mov.new_pos, letters)?;
+
+        Ok(1)
+    }
+}
+
+pub struct Costed<C> {
+    pub crane: C,
+    pub cost: usize,
+}
+
+impl<C: Crane> Crane for Costed<C> {
+    fn execute(&self, yard: &mut CargoYard, mov: &Move) -> Result<usize, MoveError> {
+        self.crane.execute(yard, mov)
+    }
+
+    fn cost_per_operation(&self) -> usize {
+        self.cost
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Simulation {
+    pub tops: String,
+    pub operations: usize,
+    pub cost: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CargoYard {
+    rows: Vec<Row>,
+}
+
+// Stacks are located by the columns their numbers occupy in the last line,
+// so a crate belongs to whichever number its brackets sit above
+impl FromStr for CargoYard {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().collect();
+        let error = |line: usize, column: usize, kind| ParseError {
+            line: line + 1,
+            column: column + 1,
+            kind,
+        };
+
+        let (numbers, drawing) = lines
+            .split_last()
+            .ok_or_else(|| error(0, 0, ParseErrorKind::MissingStackNumbers))?;
+
+        let mut stacks: Vec<(usize, usize)> = Vec::new();
+        for (i, (column, token)) in tokens(numbers).into_iter().enumerate() {
+            if token.parse() != Ok(i + 1) {
+                return Err(error(drawing.len(), column, ParseErrorKind::BadStackNumber));
+            }
+
+            stacks.push((column, column + token.chars().count() - 1));
+        }
+
+        if stacks.is_empty() {
+            return Err(error(drawing.len(), 0, ParseErrorKind::MissingStackNumbers));
+        }
+
+        let mut rows: Vec<Row> = vec![Row::new(); stacks.len()];
+
+        for (y, line) in drawing.iter().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            let mut x = 0;
+
+            while x < chars.len() {
+                match chars[x] {
+                    ' ' => x += 1,
+                    '[' => {
+                        let close = (x + 1..chars.len())
+                            .find(|i| chars[*i] == ']')
+                            .ok_or_else(|| error(y, x, ParseErrorKind::UnclosedCrate))?;
+
+                        let label: String = chars[x + 1..close].iter().collect();
+                        if label.trim().is_empty() || label.contains('[') {
+                            return Err(error(y, x, ParseErrorKind::EmptyLabel));
+                        }
+
+                        let stack = stacks
+                            .iter()
+                            .positions(|(start, end)| *start <= close && x <= *end)
+                            .exactly_one()
+                            .map_err(|_| error(y, x, ParseErrorKind::CrateOutsideStack))?;
+
+                        rows[stack].0.push(label);
+                        x = close + 1;
+                    }
+                    c => return Err(error(y, x, ParseErrorKind::UnexpectedChar(c))),
+                }
+            }
+        }
+
+        for row in rows.iter_mut() {
+            row.0.reverse();
+        }
+
+        Ok(CargoYard { rows })
+    }
+}
+
+impl CargoYard {
+    fn stack(&mut self, stack: usize) -> Result<&mut Row, MoveError> {
+        stack
+            .checked_sub(1)
+            .and_then(|i| self.rows.get_mut(i))
+            .ok_or(MoveError::NoSuchStack(stack))
+    }
+
+    fn check_amount(&mut self, stack: usize, amount: usize) -> Result<&mut Row, MoveError> {
+        let row = self.stack(stack)?;
+
+        if amount > row.0.len() {
+            return Err(MoveError::NotEnoughCrates {
+                stack,
+                requested: amount,
+                available: row.0.len(),
+            });
+        }
+
+        Ok(row)
+    }
+
+    pub fn validate(&mut self, mov: &Move) -> Result<(), MoveError> {
+        self.stack(mov.new_pos)?;
+        self.check_amount(mov.cur_pos, mov.amount)?;
+        Ok(())
+    }
+
+    // Crates come back ordered from bottom to top
+    pub fn lift(&mut self, stack: usize, amount: usize) -> Result<Vec<String>, MoveError> {
+        Ok(self.check_amount(stack, amount)?.take(amount))
+    }
 
-    let rows_amount: usize = LAST_NUM_REGEX
-        .captures(&crates)
-        .unwrap()
-        .get(1)
-        .unwrap()
-        .as_str()
-        .parse()
-        .unwrap();
+    pub fn lift_bottom(&mut self, stack: usize, amount: usize) -> Result<Vec<String>, MoveError> {
+        Ok(self
+            .check_amount(stack, amount)?
+            .0
+            .drain(..amount)
+            .collect())
+    }
 
-    let mut rows: Vec<Row> = vec![Row::new(); rows_amount];
+    pub fn place(&mut self, stack: usize, letters: Vec<String>) -> Result<(), MoveError> {
+        self.stack(stack)?.put(letters);
+        Ok(())
+    }
 
-    for (i, char) in crates.lines().flat_map(|line| {
-        CRATES_FIND_REGEX
-            .captures_iter(line)
-            .enumerate()
-            .filter_map(|(i, c)| c.get(1).map(|m| (i, m.as_str().chars().next().unwrap())))
-    }) {
-        rows[i].0.push(char);
+    pub fn apply(&mut self, mov: &Move, crane: &dyn Crane) -> Result<usize, MoveError> {
+        self.validate(mov)?;
+        crane.execute(self, mov)
     }
 
-    for mov in moves.lines().flat_map(Move::from_str) {
-        execute_move(&mov, &mut rows);
+    pub fn replay<'a>(&self, moves: &'a [Move], crane: &'a dyn Crane) -> Replay<'a> {
+        Replay {
+            yard: self.clone(),
+            moves: moves.iter(),
+            crane,
+        }
+    }
+
+    pub fn simulate(&mut self, moves: &[Move], crane: &dyn Crane) -> Result<Simulation, MoveError> {
+        let mut operations = 0;
+
+        for mov in moves {
+            operations += self.apply(mov, crane)?;
+        }
+
+        Ok(Simulation {
+            tops: self.tops(),
+            operations,
+            cost: operations * crane.cost_per_operation(),
+        })
     }
 
-    rows.iter().map(|row| row.0.first().unwrap()).collect()
+    pub fn tops(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.0.last().map_or(" ", String::as_str))
+            .collect()
+    }
+
+    // Level 0 is the bottom of the stack
+    fn crate_at(&self, row: usize, level: usize) -> Option<&str> {
+        self.rows[row].0.get(level).map(String::as_str)
+    }
+}
+
+// Every column is as wide as the widest crate or stack number, which keeps
+// the usual `[A] [B]` layout for single letter crates
+impl fmt::Display for CargoYard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+        let width = self
+            .rows
+            .iter()
+            .flat_map(|row| row.0.iter().map(|label| label.chars().count() + 2))
+            .chain([3, self.rows.len().to_string().len()])
+            .max()
+            .unwrap();
+
+        for level in (0..height).rev() {
+            let line = (0..self.rows.len())
+                .map(|row| match self.crate_at(row, level) {
+                    Some(label) => format!("{:<width$}", format!("[{label}]")),
+                    None => " ".repeat(width),
+                })
+                .join(" ");
+            writeln!(f, "{line}")?;
+        }
+
+        let numbers = (1..=self.rows.len())
+            .map(|i| format!("{i:^width$}"))
+            .join(" ");
+        write!(f, "{numbers}")
+    }
+}
+
+pub struct Replay<'a> {
+    yard: CargoYard,
+    moves: slice::Iter<'a, Move>,
+    crane: &'a dyn Crane,
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Result<CargoYard, MoveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mov = self.moves.next()?;
+
+        Some(match self.yard.apply(mov, self.crane) {
+            Ok(_) => Ok(self.yard.clone()),
+            Err(e) => {
+                // Nothing after a failed move can be trusted
+                self.moves = [].iter();
+                Err(e)
+            }
+        })
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<(CargoYard, Vec<Move>), ParseError> {
+    let (crates, moves) = input.split_once("\n\n").ok_or_else(|| ParseError {
+        line: input.lines().count() + 1,
+        column: 1,
+        kind: ParseErrorKind::MissingSeparator,
+    })?;
+
+    let yard = crates.parse()?;
+    let offset = crates.lines().count() + 1;
+    let moves = moves
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.parse().map_err(|e: ParseError| ParseError {
+                line: offset + i + 1,
+                ..e
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((yard, moves))
+}
+
+fn logic(input: &str, crane: &dyn Crane) -> Result<String, SimulationError> {
+    let (mut yard, moves) = parse_input(input)?;
+    Ok(yard.simulate(&moves, crane)?.tops)
 }
 
 #[aoc(day5, part1)]
-pub fn part1(input: &str) -> String {
-    logic(input, &Move::execute)
+pub fn part1(input: &str) -> Result<String, SimulationError> {
+    logic(input, &CrateMover9000)
 }
 
 #[aoc(day5, part2)]
-pub fn part2(input: &str) -> String {
-    logic(input, &Move::execute_same_order)
+pub fn part2(input: &str) -> Result<String, SimulationError> {
+    logic(input, &CrateMover9001)
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day5::{part1, part2};
+    use crate::day5::{
+        BottomCrane, CargoYard, Costed, CrateMover9000, CrateMover9001, LimitedCrane, MoveError,
+        ParseError, ParseErrorKind, Simulation, SimulationError, parse_input, part1, part2,
+    };
 
     #[test]
     fn test_day5() {
         let input = read_to_string("input/2022/day5.txt").unwrap();
 
-        assert_eq!("FCVRLMVQP", part1(&input));
-        assert_eq!("RWLWGJGFD", part2(&input));
+        assert_eq!("FCVRLMVQP", part1(&input).unwrap());
+        assert_eq!("RWLWGJGFD", part2(&input).unwrap());
+
+        let (yard, moves) = parse_input(&input).unwrap();
+        let drawing = input.split("\n\n").next().unwrap();
+        assert_eq!(drawing, yard.to_string());
+
+        let states: Vec<_> = yard
+            .replay(&moves, &CrateMover9000)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(moves.len(), states.len());
+        assert_eq!("FCVRLMVQP", states.last().unwrap().tops());
+        assert_eq!(states[0], states[0].to_string().parse().unwrap());
+
+        let drawing = "[A]    \n[B] [C]\n 1   2 \n\n";
+        assert_eq!(
+            " B",
+            part1(&format!("{drawing}move 2 from 1 to 2")).unwrap()
+        );
+        assert_eq!(
+            " A",
+            part2(&format!("{drawing}move 2 from 1 to 2")).unwrap()
+        );
+        assert_eq!(
+            Err(SimulationError::Move(MoveError::NoSuchStack(3))),
+            part1(&format!("{drawing}move 1 from 1 to 3"))
+        );
+        assert_eq!(
+            Err(SimulationError::Move(MoveError::NotEnoughCrates {
+                stack: 2,
+                requested: 2,
+                available: 1,
+            })),
+            part2(&format!("{drawing}move 2 from 2 to 1"))
+        );
+
+        let (yard, moves) = parse_input(&input).unwrap();
+        let total: usize = moves.iter().map(|mov| mov.amount).sum();
+        assert_eq!(
+            Simulation {
+                tops: String::from("FCVRLMVQP"),
+                operations: total,
+                cost: total,
+            },
+            yard.clone().simulate(&moves, &CrateMover9000).unwrap()
+        );
+        assert_eq!(
+            moves.len(),
+            yard.clone()
+                .simulate(&moves, &CrateMover9001)
+                .unwrap()
+                .operations
+        );
+        let limited = yard
+            .clone()
+            .simulate(&moves, &LimitedCrane { capacity: 1 })
+            .unwrap();
+        assert_eq!(
+            ("FCVRLMVQP", total),
+            (limited.tops.as_str(), limited.operations)
+        );
+
+        let (yard, moves) =
+            parse_input(&format!("{drawing}move 1 from 1 to 2\nmove 2 from 2 to 1")).unwrap();
+        let costed = Costed {
+            crane: BottomCrane,
+            cost: 3,
+        };
+        let simulation = yard.clone().simulate(&moves, &costed).unwrap();
+        assert_eq!(
+            ("B ", 2, 6),
+            (
+                simulation.tops.as_str(),
+                simulation.operations,
+                simulation.cost
+            )
+        );
+    }
+
+    #[test]
+    fn test_day5_parser() {
+        let drawing = concat!(
+            "[A]                                      [K]\n",
+            "[B] [C] [D] [E] [F] [G] [H] [I] [J] [XY] [L]\n",
+            " 1   2   3   4   5   6   7   8   9    10   11",
+        );
+        let yard: CargoYard = drawing.parse().unwrap();
+        assert_eq!("ACDEFGHIJXYK", yard.tops());
+
+        let rendered = yard.to_string();
+        assert_eq!(
+            format!("[A] {}[K] ", " ".repeat(46)),
+            rendered.lines().next().unwrap()
+        );
+        assert_eq!(yard, rendered.parse().unwrap());
+
+        let (yard, _) = parse_input(&format!("{drawing}\n\nmove 1 from 10 to 11\n")).unwrap();
+        let mut yard = yard;
+        yard.lift(10, 1).unwrap();
+        assert_eq!("ACDEFGHIJ K", yard.tops());
+
+        let error = |input: &str| parse_input(input).unwrap_err();
+        assert_eq!(
+            ParseError {
+                line: 5,
+                column: 13,
+                kind: ParseErrorKind::InvalidNumber,
+            },
+            error("[A]\n 1\n\nmove 1 from 1 to 1\nmove 1 from x to 1")
+        );
+        assert_eq!(
+            ParseErrorKind::ExpectedKeyword("to"),
+            error("[A]\n 1\n\nmove 1 from 1 into 1").kind
+        );
+        assert_eq!(
+            ParseErrorKind::TrailingInput,
+            error("[A]\n 1\n\nmove 1 from 1 to 1 now").kind
+        );
+        assert_eq!(
+            ParseErrorKind::ExpectedKeyword("to"),
+            error("[A]\n 1\n\nmove 1 from 1").kind
+        );
+        assert_eq!(
+            ParseError {
+                line: 1,
+                column: 5,
+                kind: ParseErrorKind::UnclosedCrate,
+            },
+            error("[A] [B\n 1   2\n\n")
+        );
+        assert_eq!(ParseErrorKind::EmptyLabel, error("[]\n 1\n\n").kind);
+        assert_eq!(
+            ParseErrorKind::UnexpectedChar('A'),
+            error(" A \n 1\n\n").kind
+        );
+        assert_eq!(
+            ParseErrorKind::CrateOutsideStack,
+            error("    [A]\n 1\n\n").kind
+        );
+        assert_eq!(
+            ParseError {
+                line: 2,
+                column: 6,
+                kind: ParseErrorKind::BadStackNumber,
+            },
+            error("[A]\n 1   3\n\n")
+        );
+        assert_eq!(ParseErrorKind::MissingSeparator, error("[A]\n 1").kind);
     }
 }
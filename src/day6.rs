@@ -1,58 +1,226 @@
-const ALPHALEN: usize = 26 * 2;
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
 
 #[aoc_generator(day6)]
-fn input_generator(input: &str) -> Vec<(usize, char)> {
-    input.chars().enumerate().collect()
+fn input_generator(input: &str) -> Vec<u8> {
+    input.trim_end().as_bytes().to_vec()
 }
 
-fn char_to_pos(c: char) -> u32 {
-    if c.is_uppercase() {
-        (c as u32 - 65) + 26
-    } else {
-        c as u32 - 97
+// Keeps a count of every byte value inside the window and how many of those
+// values appear more than once, so each step only touches two counters
+pub struct MarkerDetector {
+    n: usize,
+    window: VecDeque<u8>,
+    counts: [usize; 256],
+    duplicates: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            window: VecDeque::with_capacity(n + 1),
+            counts: [0; 256],
+            duplicates: 0,
+            position: 0,
+        }
+    }
+
+    // Returns the number of bytes seen so far when they end with a marker
+    pub fn push(&mut self, byte: u8) -> Option<usize> {
+        self.position += 1;
+        self.window.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+
+        if self.window.len() > self.n {
+            let old = self.window.pop_front().unwrap();
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+
+        (self.window.len() == self.n && self.duplicates == 0).then_some(self.position)
     }
 }
 
-fn logic(input: &[(usize, char)], n: usize) -> usize {
-    for skip in 0.. {
-        let mut contains = vec![0; ALPHALEN];
+pub fn distinct_windows(input: &[u8], n: usize) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut detector = MarkerDetector::new(n);
 
-        for (counter, (absolute_position, char)) in input.iter().skip(skip).enumerate() {
-            if counter == n {
-                match contains.into_iter().find(|c| *c > 1) {
-                    Some(_) => break,
-                    None => return *absolute_position,
-                }
+    input
+        .iter()
+        .filter_map(move |byte| detector.push(*byte))
+        .map(move |end| (end - n, &input[end - n..end]))
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Segment<'a> {
+    pub marker: usize,
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+// Markers don't overlap, the search for the next one only starts once the
+// previous marker is complete. Everything up to the next marker is payload
+pub fn split(input: &[u8], n: usize) -> Vec<Segment<'_>> {
+    let mut markers = Vec::new();
+    let mut start = 0;
+
+    while let Some(end) = find_marker(&input[start..], n) {
+        markers.push(start + end - n);
+        start += end;
+    }
+
+    markers
+        .iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            let offset = marker + n;
+            let end = markers.get(i + 1).copied().unwrap_or(input.len());
+
+            Segment {
+                marker: *marker,
+                offset,
+                data: &input[offset..end],
             }
+        })
+        .collect()
+}
+
+pub fn packets(input: &[u8]) -> Vec<Segment<'_>> {
+    split(input, 4)
+}
 
-            contains[char_to_pos(*char) as usize] += 1;
+pub fn messages(input: &[u8]) -> Vec<Segment<'_>> {
+    split(input, 14)
+}
+
+pub fn find_marker(input: &[u8], n: usize) -> Option<usize> {
+    let mut detector = MarkerDetector::new(n);
+    input.iter().find_map(|byte| detector.push(*byte))
+}
+
+pub struct StreamMarkers<R> {
+    reader: R,
+    buf: [u8; 4096],
+    len: usize,
+    pos: usize,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Iterator for StreamMarkers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos == self.len {
+                self.len = match self.reader.read(&mut self.buf) {
+                    Ok(0) => return None,
+                    Ok(len) => len,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Some(Err(e)),
+                };
+                self.pos = 0;
+            }
+
+            let byte = self.buf[self.pos];
+            self.pos += 1;
+
+            if let Some(marker) = self.detector.push(byte) {
+                return Some(Ok(marker));
+            }
         }
     }
+}
 
-    0
+pub fn stream_markers<R: Read>(reader: R, n: usize) -> StreamMarkers<R> {
+    StreamMarkers {
+        reader,
+        buf: [0; 4096],
+        len: 0,
+        pos: 0,
+        detector: MarkerDetector::new(n),
+    }
 }
 
 #[aoc(day6, part1)]
-pub fn part1(input: &[(usize, char)]) -> usize {
-    logic(input, 4)
+pub fn part1(input: &[u8]) -> Option<usize> {
+    find_marker(input, 4)
 }
 
 #[aoc(day6, part2)]
-pub fn part2(input: &[(usize, char)]) -> usize {
-    logic(input, 14)
+pub fn part2(input: &[u8]) -> Option<usize> {
+    find_marker(input, 14)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs::read_to_string;
+    use std::fs::{File, read_to_string};
 
-    use crate::day6::{input_generator, part1, part2};
+    use crate::day6::{
+        Segment, distinct_windows, find_marker, input_generator, messages, packets, part1, part2,
+        split, stream_markers,
+    };
 
     #[test]
     fn test_day6() {
         let input = input_generator(&read_to_string("input/2022/day6.txt").unwrap());
 
-        assert_eq!(1640, part1(&input));
-        assert_eq!(3613, part2(&input));
+        assert_eq!(Some(1640), part1(&input));
+        assert_eq!(Some(3613), part2(&input));
+
+        let mut markers = stream_markers(File::open("input/2022/day6.txt").unwrap(), 14);
+        assert_eq!(3613, markers.next().unwrap().unwrap());
+
+        assert_eq!(Some(7), find_marker(b"11.!.2x", 4));
+        assert_eq!(None, find_marker(b"aabbaabb", 3));
+        assert_eq!(
+            vec![4, 5, 6],
+            stream_markers(&b"abcdef"[..], 4)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_day6_segments() {
+        let input = input_generator(&read_to_string("input/2022/day6.txt").unwrap());
+        assert_eq!(1636, packets(&input)[0].marker);
+        assert_eq!(3613, messages(&input)[0].offset);
+        assert_eq!(
+            input.len() - 3613,
+            messages(&input)
+                .iter()
+                .map(|m| m.data.len() + 14)
+                .sum::<usize>()
+                - 14
+        );
+
+        assert_eq!(
+            vec![(0, &b"abc"[..]), (4, &b"bca"[..])],
+            distinct_windows(b"abcbbca", 3).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                Segment {
+                    marker: 0,
+                    offset: 3,
+                    data: b"aab",
+                },
+                Segment {
+                    marker: 6,
+                    offset: 9,
+                    data: b"fy",
+                },
+            ],
+            split(b"abcaabbdefy", 3)
+        );
+        assert!(split(b"aaaa", 2).is_empty());
     }
 }
@@ -1,58 +1,306 @@
-use std::{cell::RefCell, iter, rc::Rc, str::FromStr};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    str::FromStr,
+};
 
 use itertools::Itertools;
+use serde::Serialize;
 
 const MAX: u64 = 100_000;
 
 const TOTAL_DISK_SPACE: u64 = 70_000_000;
 const MIN_UNUSED_SPACE: u64 = 30_000_000;
 
-type Wrapper<T> = Rc<RefCell<T>>;
+pub type NodeId = usize;
+
+const ROOT: NodeId = 0;
 
 #[derive(Debug)]
-pub enum FS {
-    File {
-        name: String,
-        size: u64,
-        parent: Option<Wrapper<Self>>,
-    },
+struct Entry {
+    name: String,
+    parent: Option<NodeId>,
+    is_dir: bool,
+    size: u64,
+    children: Vec<NodeId>,
+    by_name: HashMap<String, NodeId>,
+}
 
-    Directory {
-        name: String,
-        files: Vec<Wrapper<Self>>,
-        parent: Option<Wrapper<Self>>,
-    },
+#[derive(Debug)]
+pub struct FS {
+    entries: Vec<Entry>,
 }
 
 impl FS {
-    fn size(&self) -> u64 {
-        match self {
-            FS::File { size, .. } => *size,
-            FS::Directory { files, .. } => files.iter().map(|file| file.borrow().size()).sum(),
+    fn new() -> Self {
+        Self {
+            entries: vec![Entry {
+                name: String::from("/"),
+                parent: None,
+                is_dir: true,
+                size: 0,
+                children: Vec::new(),
+                by_name: HashMap::new(),
+            }],
+        }
+    }
+
+    fn add(&mut self, parent: NodeId, name: String, is_dir: bool, size: u64) -> NodeId {
+        let id = self.entries.len();
+        self.entries[parent].by_name.insert(name.clone(), id);
+        self.entries[parent].children.push(id);
+        self.entries.push(Entry {
+            name,
+            parent: Some(parent),
+            is_dir,
+            size,
+            children: Vec::new(),
+            by_name: HashMap::new(),
+        });
+
+        id
+    }
+
+    // Re-listing a directory reports the same entries again, so those are
+    // matched against what is already known instead of being added twice
+    fn add_unique(
+        &mut self,
+        parent: NodeId,
+        name: String,
+        is_dir: bool,
+        size: u64,
+    ) -> Result<NodeId, ErrorKind> {
+        match self.child(parent, &name) {
+            Some(id)
+                if self.entries[id].is_dir == is_dir
+                    && (is_dir || self.entries[id].size == size) =>
+            {
+                Ok(id)
+            }
+            Some(_) => Err(ErrorKind::ConflictingEntry),
+            None => Ok(self.add(parent, name, is_dir, size)),
+        }
+    }
+
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.entries[dir].by_name.get(name).copied()
+    }
+
+    // Children are always pushed after their parent, so walking the arena
+    // backwards folds every subtree into its parent exactly once
+    fn compute_sizes(&mut self) {
+        for id in (1..self.entries.len()).rev() {
+            if let Some(parent) = self.entries[id].parent {
+                self.entries[parent].size += self.entries[id].size;
+            }
         }
     }
 
-    fn name(&self) -> String {
-        match self {
-            FS::File { name, .. } | FS::Directory { name, .. } => name.clone(),
+    pub fn root(&self) -> Node<'_> {
+        self.node(ROOT)
+    }
+
+    pub fn node(&self, id: NodeId) -> Node<'_> {
+        Node { fs: self, id }
+    }
+
+    pub fn find(&self, path: &str) -> Option<Node<'_>> {
+        self.root().find(path)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Node<'a> {
+    fs: &'a FS,
+    id: NodeId,
+}
+
+impl<'a> Node<'a> {
+    fn entry(&self) -> &'a Entry {
+        &self.fs.entries[self.id]
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.entry().name
+    }
+
+    pub fn size(&self) -> u64 {
+        self.entry().size
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.entry().is_dir
+    }
+
+    pub fn parent(&self) -> Option<Node<'a>> {
+        self.entry().parent.map(|id| self.fs.node(id))
+    }
+
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = Node<'a>> + use<'a> {
+        let fs = self.fs;
+        self.entry().children.iter().map(move |id| fs.node(*id))
+    }
+
+    pub fn path(&self) -> String {
+        let mut names: Vec<&str> = std::iter::successors(Some(*self), Node::parent)
+            .map(|node| node.name())
+            .collect();
+
+        // The root's own name is the leading slash
+        names.pop();
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    fn child_path(&self, parent_path: &str) -> String {
+        match parent_path {
+            "/" => format!("/{}", self.name()),
+            _ => format!("{parent_path}/{}", self.name()),
         }
     }
-    fn parent(&self) -> Option<Wrapper<Self>> {
-        match self {
-            FS::File { parent, .. } | FS::Directory { parent, .. } => {
-                parent.as_ref().map(Rc::clone)
+
+    pub fn find(&self, path: &str) -> Option<Node<'a>> {
+        let start = if path.starts_with('/') {
+            self.fs.root()
+        } else {
+            *self
+        };
+
+        path.split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .try_fold(start, |node, part| match part {
+                ".." => node.parent(),
+                name => self.fs.child(node.id, name).map(|id| self.fs.node(id)),
+            })
+    }
+
+    pub fn depth_first(&self) -> DepthFirst<'a> {
+        DepthFirst(vec![*self])
+    }
+
+    pub fn breadth_first(&self) -> BreadthFirst<'a> {
+        BreadthFirst(VecDeque::from([*self]))
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = Node<'a>> + use<'a> {
+        self.depth_first().filter(Node::is_dir)
+    }
+
+    // Same order as `directories`, but every path is built from its parent's
+    // instead of walking back up to the root for each directory
+    pub fn dir_sizes(&self) -> Vec<(String, u64)> {
+        let mut sizes = Vec::new();
+        let mut stack = Vec::new();
+        if self.is_dir() {
+            stack.push((*self, self.path()));
+        }
+
+        while let Some((dir, path)) = stack.pop() {
+            for child in dir.children().rev().filter(Node::is_dir) {
+                stack.push((child, child.child_path(&path)));
             }
+            sizes.push((path, dir.size()));
         }
+
+        sizes
+    }
+
+    fn kind(&self) -> &'static str {
+        if self.is_dir() { "dir" } else { "file" }
+    }
+
+    pub fn render_tree(&self) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0);
+        out
     }
 
-    fn add_file(&mut self, file: Self) {
-        if let FS::Directory { files, .. } = self {
-            files.push(Rc::new(RefCell::new(file)));
+    fn render_into(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}- {} ({}, size={})\n",
+            "  ".repeat(depth),
+            self.name(),
+            self.kind(),
+            self.size()
+        ));
+
+        for child in self.children() {
+            child.render_into(out, depth + 1);
         }
     }
+
+    pub fn du(&self) -> Vec<(String, u64)> {
+        self.dir_sizes()
+            .into_iter()
+            .sorted_by(|(a_path, a_size), (b_path, b_size)| {
+                b_size.cmp(a_size).then_with(|| a_path.cmp(b_path))
+            })
+            .collect()
+    }
+
+    pub fn render_du(&self) -> String {
+        self.du()
+            .into_iter()
+            .map(|(path, size)| format!("{size}\t{path}\n"))
+            .collect()
+    }
+
+    fn to_report(self) -> Report<'a> {
+        Report {
+            name: self.name(),
+            kind: self.kind(),
+            size: self.size(),
+            children: self.children().map(Node::to_report).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_report()).unwrap()
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<Report<'a>>,
+}
+
+pub struct DepthFirst<'a>(Vec<Node<'a>>);
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.0.pop()?;
+        self.0.extend(node.children().rev());
+        Some(node)
+    }
+}
+
+pub struct BreadthFirst<'a>(VecDeque<Node<'a>>);
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.0.pop_front()?;
+        self.0.extend(node.children());
+        Some(node)
+    }
 }
 
+#[derive(Debug, Eq, PartialEq)]
 pub enum Command {
+    Ls,
     Dir(String),
     File(u64, String),
     CdUp,
@@ -61,118 +309,176 @@ pub enum Command {
 }
 
 impl FromStr for Command {
-    type Err = ();
+    type Err = ErrorKind;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s == "$ ls" {
-            Err(())
-        } else if s.starts_with("dir ") {
-            Ok(Self::Dir(s.trim_start_matches("dir ").to_string()))
-        } else if s.starts_with("$ cd ") {
-            Ok(match s.trim_start_matches("$ cd ") {
+            Ok(Self::Ls)
+        } else if let Some(dir) = s.strip_prefix("$ cd ") {
+            Ok(match dir {
                 ".." => Self::CdUp,
                 "/" => Self::CdRoot,
+                "" => return Err(ErrorKind::InvalidLine),
                 dir => Self::Cd(dir.to_string()),
             })
+        } else if s.starts_with('$') {
+            Err(ErrorKind::UnknownCommand)
+        } else if let Some(name) = s.strip_prefix("dir ").filter(|name| !name.is_empty()) {
+            Ok(Self::Dir(name.to_string()))
         } else {
-            let (byte, name) = s.split(' ').next_tuple().unwrap();
-            Ok(Self::File(byte.parse().unwrap(), name.to_string()))
+            let (bytes, name) = s.split_once(' ').ok_or(ErrorKind::InvalidLine)?;
+            if name.is_empty() {
+                return Err(ErrorKind::InvalidLine);
+            }
+
+            Ok(Self::File(
+                bytes.parse().map_err(|_| ErrorKind::InvalidSize)?,
+                name.to_string(),
+            ))
         }
     }
 }
-#[aoc_generator(day7)]
-fn input_generator(input: &str) -> Wrapper<FS> {
-    let root = Rc::new(RefCell::new(FS::Directory {
-        name: String::from("/"),
-        files: Vec::new(),
-        parent: None,
-    }));
-
-    let mut current_directory = Rc::clone(&root);
-
-    for command in input.lines().flat_map(Command::from_str) {
-        match command {
-            Command::Dir(name) => {
-                current_directory.borrow_mut().add_file(FS::Directory {
-                    name: name.to_string(),
-                    parent: Some(Rc::clone(&current_directory)),
-                    files: Vec::new(),
-                });
-            }
-            Command::File(bytes, name) => {
-                current_directory.borrow_mut().add_file(FS::File {
-                    name: name.to_string(),
-                    parent: Some(Rc::clone(&current_directory)),
-                    size: bytes,
-                });
-            }
-            Command::CdUp => {
-                let parent = current_directory.borrow().parent().unwrap();
-                current_directory = parent;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    InvalidLine,
+    InvalidSize,
+    UnknownCommand,
+    ListingOutsideLs,
+    ConflictingEntry,
+    CdAboveRoot,
+    NotADirectory,
+    UnknownDirectory,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {:?} in `{}`", self.line, self.kind, self.text)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MissingDir {
+    #[default]
+    Create,
+    Error,
+}
+
+impl FS {
+    pub fn parse(input: &str, missing_dir: MissingDir) -> Result<FS, ParseError> {
+        let mut fs = FS::new();
+        let mut current_directory = ROOT;
+        let mut listing = false;
+
+        for (i, line) in input.lines().enumerate() {
+            let error = |kind| ParseError {
+                line: i + 1,
+                text: line.to_string(),
+                kind,
+            };
+
+            if line.trim().is_empty() {
+                continue;
             }
-            Command::CdRoot => current_directory = Rc::clone(&root),
-            Command::Cd(new_dir_name) => {
-                let c = Rc::clone(&current_directory);
-
-                if let FS::Directory { files, .. } = &*c.borrow() {
-                    current_directory = Rc::clone(
-                        files
-                            .iter()
-                            .find(|file| *(file.borrow()).name() == new_dir_name)
-                            .unwrap(),
-                    );
-                };
+
+            let command = line.parse().map_err(error)?;
+            let lists = matches!(command, Command::Ls | Command::Dir(_) | Command::File(..));
+
+            match command {
+                Command::Dir(_) | Command::File(..) if !listing => {
+                    return Err(error(ErrorKind::ListingOutsideLs));
+                }
+                Command::Dir(name) => {
+                    fs.add_unique(current_directory, name, true, 0)
+                        .map_err(error)?;
+                }
+                Command::File(bytes, name) => {
+                    fs.add_unique(current_directory, name, false, bytes)
+                        .map_err(error)?;
+                }
+                Command::Ls => {}
+                Command::CdUp => {
+                    current_directory = fs.entries[current_directory]
+                        .parent
+                        .ok_or_else(|| error(ErrorKind::CdAboveRoot))?;
+                }
+                Command::CdRoot => current_directory = ROOT,
+                Command::Cd(name) => {
+                    current_directory = match fs.child(current_directory, &name) {
+                        Some(id) if fs.entries[id].is_dir => id,
+                        Some(_) => return Err(error(ErrorKind::NotADirectory)),
+                        None if missing_dir == MissingDir::Create => {
+                            fs.add(current_directory, name, true, 0)
+                        }
+                        None => return Err(error(ErrorKind::UnknownDirectory)),
+                    };
+                }
             }
+
+            listing = lists;
         }
+
+        fs.compute_sizes();
+        Ok(fs)
     }
+}
 
-    root
+#[aoc_generator(day7)]
+fn input_generator(input: &str) -> FS {
+    FS::parse(input, MissingDir::Error).unwrap_or_else(|e| panic!("{e}"))
 }
 
 #[aoc(day7, part1)]
-pub fn part1(root: &Wrapper<FS>) -> u64 {
-    dir_collect(root).iter().filter(|b| **b <= MAX).sum()
+pub fn part1(fs: &FS) -> u64 {
+    fs.root()
+        .directories()
+        .map(|dir| dir.size())
+        .filter(|size| *size <= MAX)
+        .sum()
+}
+
+// `None` when there's already enough space, or when the files don't even fit
+// on the disk in the first place
+pub fn dir_to_delete(fs: &FS) -> Option<Node<'_>> {
+    let unused_space = TOTAL_DISK_SPACE.checked_sub(fs.root().size())?;
+    let needed_space = MIN_UNUSED_SPACE.checked_sub(unused_space)?;
+
+    fs.root()
+        .directories()
+        .filter(|dir| dir.size() >= needed_space)
+        .min_by_key(Node::size)
 }
 
 #[aoc(day7, part2)]
-pub fn part2(root: &Wrapper<FS>) -> u64 {
-    let unused_space = TOTAL_DISK_SPACE - root.borrow().size();
+pub fn part2(fs: &FS) -> u64 {
+    dir_to_delete(fs).map_or(0, |dir| dir.size())
+}
 
-    if unused_space >= MIN_UNUSED_SPACE {
-        0
-    } else {
-        let needed_space = MIN_UNUSED_SPACE - unused_space;
+// Report dumps for debugging, not answers, so they aren't registered as solutions
+pub fn tree(fs: &FS) -> String {
+    fs.root().render_tree()
+}
 
-        dir_collect(root)
-            .into_iter()
-            .sorted()
-            .find(|bytes| *bytes >= needed_space)
-            .unwrap()
-    }
-}
-
-fn dir_collect(dir: &Wrapper<FS>) -> Vec<u64> {
-    if let FS::Directory { files, .. } = &*dir.borrow() {
-        files
-            .iter()
-            .filter(|file| matches!(*file.borrow(), FS::Directory { .. }))
-            .flat_map(|file| {
-                dir_collect(file)
-                    .into_iter()
-                    .chain(iter::once(file.borrow().size()))
-                    .collect::<Vec<u64>>()
-            })
-            .collect()
-    } else {
-        Vec::new()
-    }
+pub fn du(fs: &FS) -> String {
+    fs.root().render_du()
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day7::{input_generator, part1, part2};
+    use crate::day7::{
+        ErrorKind, FS, MissingDir, ParseError, dir_to_delete, input_generator, part1, part2,
+    };
 
     #[test]
     fn test_day7() {
@@ -180,5 +486,110 @@ mod tests {
 
         assert_eq!(1297683, part1(&input));
         assert_eq!(5756764, part2(&input));
+
+        let dir = dir_to_delete(&input).unwrap();
+        assert_eq!(5756764, dir.size());
+        assert_eq!(dir.path(), input.find(&dir.path()).unwrap().path());
+        assert_eq!(
+            dir.path(),
+            dir.find("..").unwrap().find(dir.name()).unwrap().path()
+        );
+
+        let breadth_first: Vec<String> = input
+            .root()
+            .breadth_first()
+            .map(|node| node.path())
+            .collect();
+        assert_eq!("/", breadth_first[0]);
+        assert_eq!(input.root().depth_first().count(), breadth_first.len());
+        assert!(input.root().dir_sizes().contains(&(dir.path(), dir.size())));
+    }
+
+    #[test]
+    fn test_day7_report() {
+        let input = input_generator(
+            "$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\ndir e\n29116 f\n$ cd e\n$ ls\n584 i",
+        );
+
+        assert_eq!(
+            "- / (dir, size=14878214)\n  - a (dir, size=29700)\n    - e (dir, size=584)\n      - i (file, size=584)\n    - f (file, size=29116)\n  - b.txt (file, size=14848514)\n",
+            input.root().render_tree()
+        );
+        assert_eq!(
+            "14878214\t/\n29700\t/a\n584\t/a/e\n",
+            input.root().render_du()
+        );
+
+        assert_eq!("/a/e", input.find("/a/e").unwrap().path());
+        assert_eq!("/", input.root().path());
+
+        let depth = 4000;
+        let deep = FS::parse(
+            &format!("{}$ ls\n1 f", "$ cd a\n".repeat(depth)),
+            MissingDir::Create,
+        )
+        .unwrap();
+        let sizes = deep.root().dir_sizes();
+        assert_eq!(depth + 1, sizes.len());
+        assert_eq!("/a".repeat(depth), sizes[depth].0);
+        assert_eq!(sizes[depth].0, deep.find(&sizes[depth].0).unwrap().path());
+
+        let json: serde_json::Value = serde_json::from_str(&input.root().to_json()).unwrap();
+        assert_eq!("dir", json["type"]);
+        assert_eq!(29700, json["children"][0]["size"]);
+        assert_eq!(
+            "i",
+            json["children"][0]["children"][0]["children"][0]["name"]
+        );
+    }
+
+    #[test]
+    fn test_day7_parse() {
+        let relisted = FS::parse(
+            "$ ls\n10 a\ndir b\n$ cd b\n$ cd ..\n$ ls\n10 a\ndir b",
+            MissingDir::Error,
+        )
+        .unwrap();
+        assert_eq!(10, relisted.root().size());
+        assert_eq!(3, relisted.root().depth_first().count());
+
+        let transcript = "$ cd /\n$ cd x\n$ ls\n5 y";
+        assert_eq!(
+            ParseError {
+                line: 2,
+                text: String::from("$ cd x"),
+                kind: ErrorKind::UnknownDirectory,
+            },
+            FS::parse(transcript, MissingDir::Error).unwrap_err()
+        );
+        let created = FS::parse(transcript, MissingDir::Create).unwrap();
+        assert_eq!(5, created.find("/x").unwrap().size());
+
+        let error = |input| FS::parse(input, MissingDir::Create).unwrap_err();
+        assert_eq!(ErrorKind::InvalidSize, error("$ ls\nabc d").kind);
+        assert_eq!(ErrorKind::InvalidLine, error("$ ls\n123").kind);
+        assert_eq!(ErrorKind::ListingOutsideLs, error("$ cd /\n12 a").kind);
+        assert_eq!(
+            ErrorKind::ConflictingEntry,
+            error("$ ls\n12 a\n$ ls\n13 a").kind
+        );
+        assert_eq!(ErrorKind::NotADirectory, error("$ ls\n12 a\n$ cd a").kind);
+        assert_eq!(ErrorKind::CdAboveRoot, error("$ cd ..").kind);
+        assert_eq!(3, error("$ ls\n1 a\n$ rm a").line);
+        let boxed: Box<dyn std::error::Error> = Box::new(error("$ cd .."));
+        assert_eq!("line 1: CdAboveRoot in `$ cd ..`", boxed.to_string());
+
+        let oversized = FS::parse("$ ls\n80000000 a", MissingDir::Error).unwrap();
+        assert!(dir_to_delete(&oversized).is_none());
+        assert_eq!(0, part2(&oversized));
+
+        let listing: String = (0..20_000).map(|i| format!("1 f{i}\n")).collect();
+        let wide = FS::parse(
+            &format!("$ ls\n{listing}$ ls\n{listing}"),
+            MissingDir::Error,
+        )
+        .unwrap();
+        assert_eq!(20_000, wide.root().size());
+        assert_eq!("/f19999", wide.find("/f19999").unwrap().path());
     }
 }
@@ -1,113 +1,146 @@
+use std::{fs, io, path::Path};
+
+use crate::grid::{Grid, Point};
+
 #[aoc_generator(day8)]
-fn input_generator(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|s| s.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect()
+fn input_generator(input: &str) -> Grid<u32> {
+    Grid::parse(input, |c| c.to_digit(10)).unwrap()
 }
 
-fn edge_check(cord: usize, max: usize) -> bool {
-    cord == 0 || cord == max
+pub struct Forest {
+    pub visible: Grid<bool>,
+    pub scenic: Grid<usize>,
 }
 
-#[aoc(day8, part1)]
-pub fn part1(input: &[Vec<u32>]) -> usize {
-    let max_y = input.len() - 1;
-    let max_x = input[0].len() - 1;
+// Walks one line of trees looking back towards the edge it started from.
+// The stack only keeps trees that are taller than everything after them,
+// so whatever is left on top after popping is the tree that blocks the view
+fn sweep(input: &Grid<u32>, line: impl Iterator<Item = Point>, forest: &mut Forest) {
+    let mut stack: Vec<(usize, u32)> = Vec::new();
 
-    input
-        .iter()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.iter().enumerate().map(move |(x, height)| {
-                edge_check(y, max_y)
-                    || edge_check(x, max_x)
-                    || !((0..=x - 1).rev().any(|i| input[y][i] >= *height)
-                        && (x + 1..=max_x).any(|i| input[y][i] >= *height)
-                        && (0..=y - 1).rev().any(|i| input[i][x] >= *height)
-                        && (y + 1..=max_y).any(|i| input[i][x] >= *height))
-            })
+    for (i, point) in line.enumerate() {
+        let height = input[point];
+
+        while stack.last().is_some_and(|(_, h)| *h < height) {
+            stack.pop();
+        }
+
+        match stack.last() {
+            Some((blocker, _)) => forest.scenic[point] *= i - blocker,
+            None => {
+                forest.visible[point] = true;
+                forest.scenic[point] *= i;
+            }
+        }
+
+        stack.push((i, height));
+    }
+}
+
+pub fn analyse(input: &Grid<u32>) -> Forest {
+    let (width, height) = (input.width(), input.height());
+    let mut forest = Forest {
+        visible: Grid::filled(width, height, false),
+        scenic: Grid::filled(width, height, 1),
+    };
+
+    for y in 0..height {
+        sweep(input, (0..width).map(|x| (x, y)), &mut forest);
+        sweep(input, (0..width).rev().map(|x| (x, y)), &mut forest);
+    }
+
+    for x in 0..width {
+        sweep(input, (0..height).map(|y| (x, y)), &mut forest);
+        sweep(input, (0..height).rev().map(|y| (x, y)), &mut forest);
+    }
+
+    forest
+}
+
+const HEAT_COLOURS: [u8; 6] = [90, 34, 36, 32, 33, 31];
+
+impl Forest {
+    pub fn best(&self) -> Point {
+        self.scenic
+            .iter()
+            .max_by_key(|(_, score)| **score)
+            .map(|(point, _)| point)
+            .unwrap()
+    }
+
+    fn scaled_scores(&self) -> Grid<u8> {
+        let max = self.scenic[self.best()].max(1);
+        self.scenic.map(|_, score| (score * 255 / max) as u8)
+    }
+
+    pub fn scenic_pgm(&self) -> Vec<u8> {
+        let mut image = format!(
+            "P5\n{} {}\n255\n",
+            self.scenic.width(),
+            self.scenic.height()
+        )
+        .into_bytes();
+        image.extend(self.scaled_scores().iter().map(|(_, shade)| *shade));
+        image
+    }
+
+    // Visible trees are drawn in green and hidden ones in grey, both shaded by height
+    pub fn visibility_ppm(&self, heights: &Grid<u32>) -> Vec<u8> {
+        let mut image = format!(
+            "P6\n{} {}\n255\n",
+            self.visible.width(),
+            self.visible.height()
+        )
+        .into_bytes();
+        image.extend(self.visible.iter().flat_map(|(point, visible)| {
+            let shade = (heights[point] * 25) as u8 + 30;
+            if *visible {
+                [0, shade, 0]
+            } else {
+                [shade / 2, shade / 2, shade / 2]
+            }
+        }));
+        image
+    }
+
+    pub fn write_images(&self, heights: &Grid<u32>, dir: &Path) -> io::Result<()> {
+        fs::write(dir.join("scenic.pgm"), self.scenic_pgm())?;
+        fs::write(dir.join("visibility.ppm"), self.visibility_ppm(heights))
+    }
+
+    pub fn heatmap(&self, heights: &Grid<u32>) -> String {
+        let best = self.best();
+        let scaled = self.scaled_scores();
+
+        heights.render_ansi(|point, height| {
+            if point == best {
+                ('X', Some(93))
+            } else {
+                let bucket = scaled[point] as usize * HEAT_COLOURS.len() / 256;
+                (
+                    char::from_digit(*height, 10).unwrap(),
+                    Some(HEAT_COLOURS[bucket]),
+                )
+            }
         })
-        .filter(|visible| *visible)
+    }
+}
+
+#[aoc(day8, part1)]
+pub fn part1(input: &Grid<u32>) -> usize {
+    analyse(input)
+        .visible
+        .iter()
+        .filter(|(_, visible)| **visible)
         .count()
 }
 
 #[aoc(day8, part2)]
-pub fn part2(input: &[Vec<u32>]) -> usize {
-    let max_y = input.len() - 1;
-    let max_x = input[0].len() - 1;
-
-    input
+pub fn part2(input: &Grid<u32>) -> usize {
+    analyse(input)
+        .scenic
         .iter()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.iter().enumerate().map(move |(x, height)| {
-                let mut count_wall = true;
-
-                let up = (0..=y)
-                    .rev()
-                    .skip(1)
-                    .take_while(|i| {
-                        if count_wall && *height > input[*i][x] {
-                            true
-                        } else if count_wall {
-                            count_wall = false;
-                            true
-                        } else {
-                            false
-                        }
-                    })
-                    .count();
-                count_wall = true;
-
-                let down = (y..=max_y)
-                    .skip(1)
-                    .take_while(|i| {
-                        if count_wall && *height > input[*i][x] {
-                            true
-                        } else if count_wall {
-                            count_wall = false;
-                            true
-                        } else {
-                            false
-                        }
-                    })
-                    .count();
-                count_wall = true;
-
-                let left = (0..=x)
-                    .rev()
-                    .skip(1)
-                    .take_while(|i| {
-                        if count_wall && *height > input[y][*i] {
-                            true
-                        } else if count_wall {
-                            count_wall = false;
-                            true
-                        } else {
-                            false
-                        }
-                    })
-                    .count();
-                count_wall = true;
-
-                let right = (x..=max_x)
-                    .skip(1)
-                    .take_while(|i| {
-                        if count_wall && *height > input[y][*i] {
-                            true
-                        } else if count_wall {
-                            count_wall = false;
-                            true
-                        } else {
-                            false
-                        }
-                    })
-                    .count();
-
-                up * left * down * right
-            })
-        })
+        .map(|(_, score)| *score)
         .max()
         .unwrap()
 }
@@ -116,7 +149,7 @@ pub fn part2(input: &[Vec<u32>]) -> usize {
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day8::{input_generator, part1, part2};
+    use crate::day8::{analyse, input_generator, part1, part2};
 
     #[test]
     fn test_day8() {
@@ -124,5 +157,28 @@ mod tests {
 
         assert_eq!(1703, part1(&input));
         assert_eq!(496650, part2(&input));
+
+        let example = input_generator("30373\n25512\n65332\n33549\n35390");
+        let forest = analyse(&example);
+        assert_eq!(21, part1(&example));
+        assert_eq!(8, part2(&example));
+        assert_eq!(
+            "#####\n###.#\n##.##\n#.#.#\n#####\n",
+            forest.visible.render(|v| if *v { '#' } else { '.' })
+        );
+        assert_eq!(4, forest.scenic[(2, 1)]);
+        assert_eq!(8, forest.scenic[(2, 3)]);
+        assert_eq!(0, forest.scenic[(0, 2)]);
+
+        assert_eq!((2, 3), forest.best());
+        let pgm = forest.scenic_pgm();
+        assert!(pgm.starts_with(b"P5\n5 5\n255\n"));
+        assert_eq!(255, pgm[pgm.len() - 25 + 17]);
+        let ppm = forest.visibility_ppm(&example);
+        assert_eq!(b"P6\n5 5\n255\n".len() + 75, ppm.len());
+
+        let heatmap = forest.heatmap(&example);
+        assert_eq!(1, heatmap.matches('X').count());
+        assert!(heatmap.contains("\x1b[93mX\x1b[0m"));
     }
 }
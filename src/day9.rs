@@ -1,103 +1,497 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::HashSet,
+    hash::BuildHasher,
+    ops::{Add, AddAssign, Sub},
+    str::FromStr,
+};
 
-#[derive(Debug, Clone, Copy)]
-pub enum Move {
-    U,
-    D,
-    R,
-    L,
-}
+use rustc_hash::FxHashSet;
+
+use crate::grid::Grid;
 
+// Positions and head moves are vectors of any dimension. The first axis goes
+// right, the second goes down and the third goes forward
 #[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
-pub struct Pos {
-    x: i64,
-    y: i64,
+pub struct Pos<const N: usize = 2>(pub [i64; N]);
+
+impl<const N: usize> Pos<N> {
+    pub const fn origin() -> Self {
+        Pos([0; N])
+    }
+
+    fn map(self, f: impl Fn(i64) -> i64) -> Self {
+        Pos(self.0.map(f))
+    }
+
+    // Distance when diagonal steps are allowed
+    pub fn chebyshev(&self) -> i64 {
+        self.0.iter().map(|c| c.abs()).max().unwrap_or(0)
+    }
+
+    pub fn manhattan(&self) -> i64 {
+        self.0.iter().map(|c| c.abs()).sum()
+    }
 }
 
-impl Pos {
-    fn new() -> Pos {
-        Pos { x: 0, y: 0 }
+impl<const N: usize> Add for Pos<N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Pos(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+}
+
+impl<const N: usize> AddAssign for Pos<N> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
     }
+}
+
+impl<const N: usize> Sub for Pos<N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Pos(std::array::from_fn(|i| self.0[i] - other.0[i]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Move<const N: usize = 2> {
+    pub direction: Pos<N>,
+    pub count: usize,
+}
 
-    fn execute(&mut self, m: &Move) {
-        match m {
-            Move::R => self.x += 1,
-            Move::L => self.x -= 1,
-            Move::U => self.y -= 1,
-            Move::D => self.y += 1,
+// Decides where a knot goes after the knot in front of it has moved
+pub trait FollowRule<const N: usize> {
+    fn follow(&self, knot: Pos<N>, leader: Pos<N>) -> Pos<N>;
+}
+
+// Knots move one step towards their leader, diagonally if needed, as soon as
+// they are more than the given number of steps away. `Slack(1)` is the puzzle
+#[derive(Debug, Clone, Copy)]
+pub struct Slack(pub i64);
+
+impl<const N: usize> FollowRule<N> for Slack {
+    fn follow(&self, knot: Pos<N>, leader: Pos<N>) -> Pos<N> {
+        let delta = leader - knot;
+
+        if delta.chebyshev() > self.0 {
+            knot + delta.map(i64::signum)
+        } else {
+            knot
         }
     }
+}
+
+// Knots have to share a side with their leader and can only move along one
+// axis at a time, the one with the biggest gap
+#[derive(Debug, Clone, Copy)]
+pub struct Orthogonal;
+
+impl<const N: usize> FollowRule<N> for Orthogonal {
+    fn follow(&self, knot: Pos<N>, leader: Pos<N>) -> Pos<N> {
+        let delta = leader - knot;
+
+        if delta.manhattan() <= 1 {
+            return knot;
+        }
 
-    fn is_touching(&self, other: &Self) -> bool {
-        (self.x == other.x || self.x == other.x + 1 || self.x == other.x - 1)
-            && (self.y == other.y || self.y == other.y + 1 || self.y == other.y - 1)
+        let axis = (0..N).rev().max_by_key(|i| delta.0[*i].abs()).unwrap();
+        let mut step = Pos::origin();
+        step.0[axis] = delta.0[axis].signum();
+        knot + step
     }
+}
+
+const AXES: [(char, usize, i64); 6] = [
+    ('R', 0, 1),
+    ('L', 0, -1),
+    ('D', 1, 1),
+    ('U', 1, -1),
+    ('F', 2, 1),
+    ('B', 2, -1),
+];
+
+// Diagonal moves combine letters, `UR` goes up and right at the same time
+fn parse_direction<const N: usize>(s: &str) -> Result<Pos<N>, String> {
+    let mut direction = Pos::origin();
 
-    fn follow(&mut self, other: &Self) {
-        if !self.is_touching(other) {
-            self.x += (other.x - self.x).clamp(-1, 1);
-            self.y += (other.y - self.y).clamp(-1, 1);
+    for c in s.chars() {
+        let (_, axis, step) = AXES
+            .iter()
+            .find(|(letter, _, _)| *letter == c)
+            .ok_or_else(|| format!("Invalid direction {s}"))?;
+
+        if *axis >= N || direction.0[*axis] != 0 {
+            return Err(format!("Invalid direction {s}"));
         }
+        direction.0[*axis] = *step;
+    }
+
+    if direction == Pos::origin() {
+        return Err(format!("Invalid direction {s}"));
     }
+
+    Ok(direction)
 }
 
-impl FromStr for Move {
+impl<const N: usize> FromStr for Move<N> {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.split_whitespace().next().unwrap() {
-            "U" => Move::U,
-            "D" => Move::D,
-            "R" => Move::R,
-            "L" => Move::L,
-            _ => return Err(String::from("Invalid")),
+        let (direction, count) = s
+            .split_once(' ')
+            .ok_or_else(|| format!("Invalid move {s}"))?;
+
+        Ok(Move {
+            direction: parse_direction(direction)?,
+            count: count
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid count {count}"))?,
         })
     }
 }
 
 #[aoc_generator(day9)]
 fn input_generator(input: &str) -> Vec<Move> {
-    input
-        .lines()
-        .flat_map(|line| {
-            vec![line.parse().unwrap(); line.split_whitespace().nth(1).unwrap().parse().unwrap()]
-        })
-        .collect()
+    input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-fn logic(moves: &[Move], l: usize) -> usize {
-    let mut pieces = vec![Pos::new(); l];
-    let mut visited: HashSet<Pos> = HashSet::from([Pos::new()]);
+// The history holds every position a knot has been in, one entry per step,
+// starting with the origin. It's only recorded when asked for since it grows
+// with the number of steps times the number of knots
+#[derive(Debug, Clone)]
+pub struct Rope<const N: usize = 2, R = Slack> {
+    knots: Vec<Pos<N>>,
+    rule: R,
+    history: Option<Vec<Vec<Pos<N>>>>,
+}
 
-    for m in moves.iter() {
-        pieces[0].execute(m);
+impl Rope {
+    pub fn new(length: usize) -> Self {
+        Self::with_rule(length, Slack(1))
+    }
+}
+
+impl<const N: usize, R: FollowRule<N>> Rope<N, R> {
+    pub fn with_rule(length: usize, rule: R) -> Self {
+        Self {
+            knots: vec![Pos::origin(); length],
+            rule,
+            history: None,
+        }
+    }
+
+    pub fn with_history(mut self) -> Self {
+        self.history = Some(self.knots.iter().map(|knot| vec![*knot]).collect());
+        self
+    }
+
+    pub fn knots(&self) -> &[Pos<N>] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Pos<N> {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Pos<N> {
+        *self.knots.last().unwrap()
+    }
+
+    pub fn history(&self, knot: usize) -> &[Pos<N>] {
+        self.history.as_ref().map_or(&[], |history| &history[knot])
+    }
+
+    pub fn step(&mut self, direction: Pos<N>) {
+        self.knots[0] += direction;
+
+        for i in 1..self.knots.len() {
+            self.knots[i] = self.rule.follow(self.knots[i], self.knots[i - 1]);
+        }
+
+        if let Some(history) = &mut self.history {
+            for (positions, knot) in history.iter_mut().zip(&self.knots) {
+                positions.push(*knot);
+            }
+        }
+    }
+
+    // Calls `f` with the tail after every single step of the move
+    pub fn apply(&mut self, m: &Move<N>, mut f: impl FnMut(Pos<N>)) {
+        for _ in 0..m.count {
+            self.step(m.direction);
+            f(self.tail());
+        }
+    }
+}
+
+impl<R: FollowRule<2>> Rope<2, R> {
+    // The puzzle labels the knots H, 1, 2, ... and calls the second knot T
+    // when there are only two of them
+    fn label(&self, knot: usize) -> char {
+        match knot {
+            0 => 'H',
+            1 if self.knots.len() == 2 => 'T',
+            _ => char::from_digit(knot as u32 % 36, 36).unwrap(),
+        }
+    }
+
+    // Covers the origin and everything the rope has been through so the
+    // frame doesn't move around between steps
+    fn canvas(&self) -> (Grid<char>, Pos) {
+        let positions = self.history.iter().flatten().flatten().chain(&self.knots);
+
+        let (mut min, mut max) = (Pos::origin(), Pos::origin());
+        for Pos([x, y]) in positions {
+            min = Pos([min.0[0].min(*x), min.0[1].min(*y)]);
+            max = Pos([max.0[0].max(*x), max.0[1].max(*y)]);
+        }
+
+        let Pos([width, height]) = max - min;
+        (
+            Grid::filled(width as usize + 1, height as usize + 1, '.'),
+            min,
+        )
+    }
+
+    fn draw(grid: &mut Grid<char>, origin: Pos, pos: Pos, c: char) {
+        let Pos([x, y]) = pos - origin;
+        grid[(x as usize, y as usize)] = c;
+    }
+
+    // Earlier knots are drawn on top of later ones, like in the puzzle
+    pub fn render(&self) -> String {
+        let (mut grid, min) = self.canvas();
+        Self::draw(&mut grid, min, Pos::origin(), 's');
+
+        for (i, knot) in self.knots.iter().enumerate().rev() {
+            Self::draw(&mut grid, min, *knot, self.label(i));
+        }
+
+        grid.render(|c| *c)
+    }
+
+    pub fn render_trail(&self) -> String {
+        let (mut grid, min) = self.canvas();
 
-        for i in 1..pieces.len() {
-            let (first, second) = pieces.split_at_mut(i);
-            second.first_mut().unwrap().follow(first.last().unwrap());
+        for pos in self.history(self.knots.len() - 1) {
+            Self::draw(&mut grid, min, *pos, '#');
         }
+        Self::draw(&mut grid, min, Pos::origin(), 's');
 
-        visited.insert(*pieces.last().unwrap());
+        grid.render(|c| *c)
     }
+}
+
+// Keeps track of the cells the tail has been in
+pub trait Visited<const N: usize> {
+    fn insert(&mut self, pos: Pos<N>);
+
+    fn count(&self) -> usize;
+}
+
+impl<const N: usize, S: BuildHasher> Visited<N> for HashSet<Pos<N>, S> {
+    fn insert(&mut self, pos: Pos<N>) {
+        HashSet::insert(self, pos);
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Bounds<const N: usize> {
+    pub min: Pos<N>,
+    pub max: Pos<N>,
+}
+
+impl<const N: usize> Bounds<N> {
+    // Knots only ever move towards the knot in front of them, so the whole
+    // rope stays inside the box the head has been through. The head can be
+    // moved a whole move at a time since the box is all that matters here
+    pub fn of_path(moves: &[Move<N>]) -> Self {
+        let mut head = Pos::origin();
+        let mut bounds = Bounds {
+            min: head,
+            max: head,
+        };
+
+        for m in moves {
+            head += m.direction.map(|c| c * m.count as i64);
+            bounds.min = Pos(std::array::from_fn(|i| bounds.min.0[i].min(head.0[i])));
+            bounds.max = Pos(std::array::from_fn(|i| bounds.max.0[i].max(head.0[i])));
+        }
+
+        bounds
+    }
+
+    fn size(&self) -> Option<[usize; N]> {
+        let mut size = [0; N];
+
+        for (i, size) in size.iter_mut().enumerate() {
+            let span = self.max.0[i].abs_diff(self.min.0[i]);
+            *size = usize::try_from(span).ok()?.checked_add(1)?;
+        }
+
+        Some(size)
+    }
+
+    pub fn cells(&self) -> Option<usize> {
+        self.size()?
+            .iter()
+            .try_fold(1usize, |cells, size| cells.checked_mul(*size))
+    }
+}
+
+// One bit per cell of the bounding box. Follow rules that let knots wander
+// off still work, anything outside the box goes into a set instead
+pub struct Bitmap<const N: usize> {
+    min: Pos<N>,
+    size: [usize; N],
+    bits: Vec<u64>,
+    count: usize,
+    outside: FxHashSet<Pos<N>>,
+}
+
+impl<const N: usize> Bitmap<N> {
+    // `None` when the bitmap would take more than `MAX_BITMAP_CELLS` bits
+    pub fn new(bounds: &Bounds<N>) -> Option<Self> {
+        let cells = bounds.cells().filter(|cells| *cells <= MAX_BITMAP_CELLS)?;
+
+        Some(Self {
+            min: bounds.min,
+            size: bounds.size()?,
+            bits: vec![0; cells.div_ceil(64)],
+            count: 0,
+            outside: FxHashSet::default(),
+        })
+    }
+
+    fn index(&self, pos: Pos<N>) -> Option<usize> {
+        let mut index = 0;
+
+        for i in (0..N).rev() {
+            let offset = usize::try_from(pos.0[i] - self.min.0[i])
+                .ok()
+                .filter(|offset| *offset < self.size[i])?;
+            index = index * self.size[i] + offset;
+        }
+
+        Some(index)
+    }
+}
+
+impl<const N: usize> Visited<N> for Bitmap<N> {
+    fn insert(&mut self, pos: Pos<N>) {
+        match self.index(pos) {
+            Some(index) => {
+                let (word, bit) = (index / 64, 1 << (index % 64));
+                if self.bits[word] & bit == 0 {
+                    self.bits[word] |= bit;
+                    self.count += 1;
+                }
+            }
+            None => {
+                self.outside.insert(pos);
+            }
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count + self.outside.len()
+    }
+}
+
+// 32 MiB worth of bits
+const MAX_BITMAP_CELLS: usize = 1 << 28;
 
-    visited.len()
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backend {
+    SipHash,
+    FxHash,
+    // A bitmap when the bounding box is small enough, FxHash otherwise
+    Bitmap,
+}
+
+fn track<const N: usize, R: FollowRule<N>>(
+    moves: &[Move<N>],
+    mut rope: Rope<N, R>,
+    mut visited: impl Visited<N>,
+) -> usize {
+    visited.insert(rope.tail());
+
+    for m in moves.iter() {
+        rope.apply(m, |tail| visited.insert(tail));
+    }
+
+    visited.count()
+}
+
+pub fn logic<const N: usize, R: FollowRule<N>>(
+    moves: &[Move<N>],
+    rope: Rope<N, R>,
+    backend: Backend,
+) -> usize {
+    match backend {
+        Backend::SipHash => track(moves, rope, HashSet::new()),
+        Backend::FxHash => track(moves, rope, FxHashSet::default()),
+        Backend::Bitmap => match Bitmap::new(&Bounds::of_path(moves)) {
+            Some(bitmap) => track(moves, rope, bitmap),
+            None => track(moves, rope, FxHashSet::default()),
+        },
+    }
 }
 
 #[aoc(day9, part1)]
 pub fn part1(moves: &[Move]) -> usize {
-    logic(moves, 2)
+    logic(moves, Rope::new(2), Backend::SipHash)
 }
 
 #[aoc(day9, part2)]
 pub fn part2(moves: &[Move]) -> usize {
-    logic(moves, 10)
+    logic(moves, Rope::new(10), Backend::SipHash)
+}
+
+#[aoc(day9, part1, FxHash)]
+pub fn part1_fxhash(moves: &[Move]) -> usize {
+    logic(moves, Rope::new(2), Backend::FxHash)
+}
+
+#[aoc(day9, part1, Bitmap)]
+pub fn part1_bitmap(moves: &[Move]) -> usize {
+    logic(moves, Rope::new(2), Backend::Bitmap)
+}
+
+#[aoc(day9, part2, FxHash)]
+pub fn part2_fxhash(moves: &[Move]) -> usize {
+    logic(moves, Rope::new(10), Backend::FxHash)
+}
+
+#[aoc(day9, part2, Bitmap)]
+pub fn part2_bitmap(moves: &[Move]) -> usize {
+    logic(moves, Rope::new(10), Backend::Bitmap)
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
-    use crate::day9::{input_generator, part1, part2};
+    use crate::day9::{
+        Backend, Bitmap, Bounds, FollowRule, Move, Orthogonal, Pos, Rope, Slack, input_generator,
+        logic, part1, part1_bitmap, part1_fxhash, part2, part2_bitmap, part2_fxhash,
+    };
+
+    // Walks off the bottom of the map whatever the head does
+    struct Drift;
+
+    impl FollowRule<2> for Drift {
+        fn follow(&self, knot: Pos, _: Pos) -> Pos {
+            knot + Pos([0, 1])
+        }
+    }
 
     #[test]
     fn test_day9() {
@@ -105,5 +499,142 @@ mod tests {
 
         assert_eq!(6256, part1(&input));
         assert_eq!(2665, part2(&input));
+        assert_eq!(6256, part1_fxhash(&input));
+        assert_eq!(6256, part1_bitmap(&input));
+        assert_eq!(2665, part2_fxhash(&input));
+        assert_eq!(2665, part2_bitmap(&input));
+
+        let example = input_generator("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n");
+        assert_eq!(
+            Move {
+                direction: Pos([0, -1]),
+                count: 4,
+            },
+            example[1]
+        );
+        assert_eq!(13, part1(&example));
+        assert_eq!(1, part2(&example));
+
+        let mut rope = Rope::new(2).with_history();
+        for m in example.iter() {
+            rope.apply(m, |_| {});
+        }
+        assert_eq!(25, rope.history(0).len());
+        assert_eq!("......\n......\n.TH...\n......\ns.....\n", rope.render());
+        assert_eq!(
+            "..##..\n...##.\n.####.\n....#.\ns###..\n",
+            rope.render_trail()
+        );
+
+        let mut rope = Rope::new(10).with_history();
+        rope.apply(&"R 5".parse().unwrap(), |_| {});
+        rope.apply(&"U 8".parse().unwrap(), |_| {});
+        assert_eq!(
+            ".....H\n.....1\n.....2\n.....3\n....54\n...6..\n..7...\n.8....\n9.....\n",
+            rope.render()
+        );
+        assert!(Rope::new(10).history(0).is_empty());
+    }
+
+    #[test]
+    fn test_day9_rules() {
+        let mut rope = Rope::new(2);
+        rope.apply(&"UR 3".parse().unwrap(), |_| {});
+        assert_eq!((Pos([3, -3]), Pos([2, -2])), (rope.head(), rope.tail()));
+
+        let mut rope = Rope::with_rule(2, Orthogonal);
+        rope.apply(&"R 2".parse().unwrap(), |_| {});
+        rope.apply(&"U 2".parse().unwrap(), |_| {});
+        assert_eq!(Pos([2, -1]), rope.tail());
+
+        let mut rope = Rope::with_rule(2, Slack(3));
+        rope.apply(&"R 5".parse().unwrap(), |_| {});
+        assert_eq!(Pos([2, 0]), rope.tail());
+
+        let moves: Vec<Move<3>> = ["F 2", "RUF 1", "B 4"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        let mut rope = Rope::with_rule(2, Slack(1));
+        rope.apply(&moves[0], |_| {});
+        rope.apply(&moves[1], |_| {});
+        assert_eq!(Pos([1, -1, 2]), rope.tail());
+        assert_eq!(
+            2,
+            logic(&moves, Rope::with_rule(3, Slack(1)), Backend::Bitmap)
+        );
+
+        assert!("F 1".parse::<Move>().is_err());
+        assert!("RL 1".parse::<Move>().is_err());
+        assert!("X 1".parse::<Move<3>>().is_err());
+    }
+
+    #[test]
+    fn test_day9_backends() {
+        let input = input_generator(&read_to_string("input/2022/day9.txt").unwrap());
+        let example = input_generator("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n");
+        assert_eq!(
+            Bounds {
+                min: Pos([0, -4]),
+                max: Pos([5, 0]),
+            },
+            Bounds::of_path(&example)
+        );
+        assert_eq!(Some(30), Bounds::of_path(&example).cells());
+
+        let mut seed: u64 = 2022;
+        let mut random = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        let generated: Vec<Move> = (0..300)
+            .map(|_| Move {
+                direction: [Pos([1, 0]), Pos([-1, 0]), Pos([0, 1]), Pos([0, -1])]
+                    [random(4) as usize],
+                count: random(50) as usize + 1,
+            })
+            .collect();
+
+        for moves in [&input, &generated] {
+            for length in [2, 10] {
+                let expected = logic(moves, Rope::new(length), Backend::SipHash);
+                for backend in [Backend::FxHash, Backend::Bitmap] {
+                    assert_eq!(expected, logic(moves, Rope::new(length), backend));
+                }
+            }
+        }
+
+        let moves = [Move {
+            direction: Pos([1, 0]),
+            count: 3,
+        }];
+        assert_eq!(4, logic(&moves, Rope::with_rule(2, Drift), Backend::Bitmap));
+
+        let huge = Bounds {
+            min: Pos([0, 0]),
+            max: Pos([1 << 20, 1 << 20]),
+        };
+        assert!(Bitmap::new(&huge).is_none());
+        let unrepresentable = Bounds {
+            min: Pos([i64::MIN, 0]),
+            max: Pos([i64::MAX, 0]),
+        };
+        assert_eq!(None, unrepresentable.cells());
+        assert!(Bitmap::new(&unrepresentable).is_none());
+
+        // Too big for a bitmap, so this falls back to FxHash
+        let far = [
+            Move {
+                direction: Pos([1, 1]),
+                count: 1,
+            },
+            Move {
+                direction: Pos([-1, -1]),
+                count: 1 << 15,
+            },
+        ];
+        assert_eq!((1 << 15) + 1, logic(&far, Rope::new(1), Backend::Bitmap));
     }
 }
@@ -0,0 +1,237 @@
+use std::ops::{Index, IndexMut};
+
+pub type Point = (usize, usize);
+pub type Direction = (isize, isize);
+
+pub const ORTHOGONAL: [Direction; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+pub const DIAGONAL: [Direction; 4] = [(1, -1), (1, 1), (-1, 1), (-1, -1)];
+pub const ALL_DIRECTIONS: [Direction; 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> Option<T>) -> Option<Self> {
+        Self::from_rows(
+            input
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| line.chars().map(&mut f).collect())
+                .collect::<Option<_>>()?,
+        )
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): Point) -> Option<&T> {
+        (x < self.width && y < self.height).then(|| &self.cells[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, (x, y): Point) -> Option<&mut T> {
+        (x < self.width && y < self.height).then(|| &mut self.cells[y * self.width + x])
+    }
+
+    pub fn offset(&self, (x, y): Point, (dx, dy): Direction) -> Option<Point> {
+        let x = x.checked_add_signed(dx)?;
+        let y = y.checked_add_signed(dy)?;
+
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .filter_map(move |direction| self.offset(point, direction))
+    }
+
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        ALL_DIRECTIONS
+            .into_iter()
+            .filter_map(move |direction| self.offset(point, direction))
+    }
+
+    pub fn ray(&self, point: Point, direction: Direction) -> impl Iterator<Item = Point> + '_ {
+        std::iter::successors(self.offset(point, direction), move |p| {
+            self.offset(*p, direction)
+        })
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + use<T> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn position(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(point, _)| point)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> {
+        self.cells[y * self.width..(y + 1) * self.width].iter()
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(
+            x < self.width,
+            "column {x} out of bounds for width {}",
+            self.width
+        );
+        (0..self.height).map(move |y| &self.cells[y * self.width + x])
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(Point, &T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.iter().map(|(point, value)| f(point, value)).collect(),
+        }
+    }
+
+    pub fn transpose(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|x| self.column(x).cloned())
+                .collect(),
+        }
+    }
+
+    pub fn render(&self, f: impl Fn(&T) -> char) -> String {
+        self.rows()
+            .map(|row| {
+                row.iter()
+                    .map(&f)
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    // The closure picks the character and an optional ANSI colour code for every cell
+    pub fn render_ansi(&self, f: impl Fn(Point, &T) -> (char, Option<u8>)) -> String {
+        let mut out = String::new();
+
+        for (point, value) in self.iter() {
+            match f(point, value) {
+                (c, Some(colour)) => out.push_str(&format!("\x1b[{colour}m{c}\x1b[0m")),
+                (c, None) => out.push(c),
+            }
+
+            if point.0 + 1 == self.width {
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point).expect("Point out of bounds")
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut Self::Output {
+        self.get_mut(point).expect("Point out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse("abc\ndef\n", Some).unwrap();
+
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(Some(&'f'), grid.get((2, 1)));
+        assert_eq!(None, grid.get((3, 0)));
+        assert_eq!(
+            vec![(1, 0), (2, 1), (0, 1)],
+            grid.neighbours4((1, 1)).collect::<Vec<_>>()
+        );
+        assert_eq!(5, grid.neighbours8((1, 1)).count());
+        assert_eq!(
+            vec![(1, 0), (2, 0)],
+            grid.ray((0, 0), (1, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!("be", grid.column(1).collect::<String>());
+        assert_eq!("def", grid.row(1).collect::<String>());
+        assert_eq!("ad\nbe\ncf\n", grid.transpose().render(|c| *c));
+        assert_eq!(Some((1, 1)), grid.position(|c| *c == 'e'));
+        assert_eq!(
+            "\x1b[94ma\x1b[0mbc\ndef\n",
+            grid.render_ansi(|point, c| (*c, (point == (0, 0)).then_some(94)))
+        );
+        assert!(Grid::parse("ab\nc", Some).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 out of bounds for width 3")]
+    fn test_grid_column_out_of_bounds() {
+        Grid::parse("abc\ndef\n", Some).unwrap().column(3);
+    }
+}
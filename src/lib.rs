@@ -1,4 +1,3 @@
-#![feature(iter_next_chunk)]
 #![feature(let_chains)]
 use aoc_runner_derive::aoc_lib;
 
@@ -7,9 +6,6 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
-#[macro_use]
-extern crate lazy_static;
-
 pub mod day1;
 pub mod day2;
 pub mod day3;
@@ -24,4 +20,6 @@ pub mod day11;
 pub mod day12;
 pub mod day13;
 
+pub mod grid;
+
 aoc_lib! { year = 2022 }